@@ -0,0 +1,8 @@
+CREATE TABLE failed_pages (
+    url TEXT NOT NULL UNIQUE,
+    state TEXT, /* FindingLetters, IteratingCourses, ScrapingCourse */
+    error_kind TEXT NOT NULL, /* fetch, status, parse, error_page */
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    PRIMARY KEY(url)
+);
@@ -0,0 +1,2 @@
+DROP TABLE unrecognized_content;
+DROP TABLE runs;
@@ -0,0 +1,19 @@
+CREATE TABLE runs (
+    id INTEGER NOT NULL,
+    started_at TEXT NOT NULL, /* RFC 3339 */
+    PRIMARY KEY(id)
+);
+
+CREATE TABLE unrecognized_content (
+    run INTEGER NOT NULL,
+    /**/
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    kind TEXT NOT NULL, /* header, field */
+    name TEXT NOT NULL,
+    raw_value TEXT,
+    snippet TEXT NOT NULL,
+    PRIMARY KEY(run, institution, course, kind, name),
+    FOREIGN KEY(run) REFERENCES runs(id)
+);
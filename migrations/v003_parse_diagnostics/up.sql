@@ -0,0 +1,13 @@
+CREATE TABLE parse_diagnostics (
+    run INTEGER NOT NULL,
+    /**/
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    section TEXT NOT NULL, /* characteristics, exams */
+    field TEXT NOT NULL,
+    raw_value TEXT NOT NULL,
+    error TEXT NOT NULL,
+    PRIMARY KEY(run, institution, course, section, field, raw_value),
+    FOREIGN KEY(run) REFERENCES runs(id)
+);
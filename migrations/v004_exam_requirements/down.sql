@@ -0,0 +1,2 @@
+DROP TABLE exam_requirements;
+DROP TABLE minimum_grades;
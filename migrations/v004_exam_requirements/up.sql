@@ -0,0 +1,23 @@
+/*
+ * A course needs every choice, a choice any one of its options, an option every one of its
+ * exams. Mandatory exams are choices with a single option.
+ */
+CREATE TABLE exam_requirements (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    choice INTEGER NOT NULL,
+    option INTEGER NOT NULL,
+    exam TEXT NOT NULL,
+    PRIMARY KEY(institution, course, choice, option, exam),
+    FOREIGN KEY(exam) REFERENCES exams(code)
+);
+
+CREATE TABLE minimum_grades (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    application REAL, /* 0-200 */
+    exams REAL, /* 0-200, each exam */
+    PRIMARY KEY(institution, course)
+);
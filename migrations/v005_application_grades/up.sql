@@ -0,0 +1,20 @@
+CREATE TABLE formulas (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    secondary_average INTEGER, /* % */
+    exams INTEGER, /* %, of the exams' average */
+    PRIMARY KEY(institution, course)
+);
+
+CREATE TABLE statistics (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    year INTEGER NOT NULL,
+    phase INTEGER NOT NULL, /* 1, 2, 3 */
+    vacancies INTEGER,
+    placed INTEGER,
+    last_placed_grade REAL, /* 0-200 */
+    PRIMARY KEY(institution, course, year, phase)
+);
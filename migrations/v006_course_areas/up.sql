@@ -0,0 +1,8 @@
+CREATE TABLE course_areas (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    cnaef_area TEXT NOT NULL,
+    PRIMARY KEY(institution, course),
+    FOREIGN KEY(cnaef_area) REFERENCES cnaef_areas(code)
+);
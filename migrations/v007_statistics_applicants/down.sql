@@ -0,0 +1 @@
+ALTER TABLE statistics DROP COLUMN applicants;
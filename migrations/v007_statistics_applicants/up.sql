@@ -0,0 +1 @@
+ALTER TABLE statistics ADD COLUMN applicants INTEGER;
@@ -0,0 +1,44 @@
+DROP TABLE guide_courses;
+
+DROP TABLE exam_requirements;
+DROP TABLE minimum_grades;
+DROP TABLE formulas;
+DROP TABLE course_areas;
+
+CREATE TABLE exam_requirements (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    choice INTEGER NOT NULL,
+    option INTEGER NOT NULL,
+    exam TEXT NOT NULL,
+    PRIMARY KEY(institution, course, choice, option, exam),
+    FOREIGN KEY(exam) REFERENCES exams(code)
+);
+
+CREATE TABLE minimum_grades (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    application REAL, /* 0-200 */
+    exams REAL, /* 0-200, each exam */
+    PRIMARY KEY(institution, course)
+);
+
+CREATE TABLE formulas (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    secondary_average INTEGER, /* % */
+    exams INTEGER, /* %, of the exams' average */
+    PRIMARY KEY(institution, course)
+);
+
+CREATE TABLE course_areas (
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    cnaef_area TEXT NOT NULL,
+    PRIMARY KEY(institution, course),
+    FOREIGN KEY(cnaef_area) REFERENCES cnaef_areas(code)
+);
@@ -0,0 +1,64 @@
+/*
+ * Admission requirements are kept per guide year. What was saved before doesn't say which guide
+ * it came from, so it's dropped and the next crawl saves it again.
+ */
+DROP TABLE exam_requirements;
+DROP TABLE minimum_grades;
+DROP TABLE formulas;
+DROP TABLE course_areas;
+
+/*
+ * A course needs every choice, a choice any one of its options, an option every one of its
+ * exams. Mandatory exams are choices with a single option.
+ */
+CREATE TABLE exam_requirements (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    choice INTEGER NOT NULL,
+    option INTEGER NOT NULL,
+    exam TEXT NOT NULL,
+    PRIMARY KEY(guide_year, institution, course, choice, option, exam),
+    FOREIGN KEY(exam) REFERENCES exams(code)
+);
+
+CREATE TABLE minimum_grades (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    application REAL, /* 0-200 */
+    exams REAL, /* 0-200, each exam */
+    PRIMARY KEY(guide_year, institution, course)
+);
+
+CREATE TABLE formulas (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    secondary_average INTEGER, /* % */
+    exams INTEGER, /* %, of the exams' average */
+    PRIMARY KEY(guide_year, institution, course)
+);
+
+CREATE TABLE course_areas (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    cnaef_area TEXT NOT NULL,
+    PRIMARY KEY(guide_year, institution, course),
+    FOREIGN KEY(cnaef_area) REFERENCES cnaef_areas(code)
+);
+
+/* The courses each guide has */
+CREATE TABLE guide_courses (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    name TEXT,
+    PRIMARY KEY(guide_year, institution, course)
+);
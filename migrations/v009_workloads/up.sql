@@ -0,0 +1,24 @@
+/* Durations and ECTS that don't add up, or are missing */
+CREATE TABLE anomalies (
+    run INTEGER NOT NULL,
+    /**/
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    kind TEXT NOT NULL, /* missing_duration, missing_unit, unknown_unit, missing_ects, unusual_ects_per_year */
+    detail TEXT NOT NULL,
+    PRIMARY KEY(run, institution, course, kind),
+    FOREIGN KEY(run) REFERENCES runs(id)
+);
+
+/* Duration in months, and ECTS per 12 months */
+CREATE TABLE workloads (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    months INTEGER,
+    ects INTEGER,
+    ects_per_year REAL,
+    PRIMARY KEY(guide_year, institution, course)
+);
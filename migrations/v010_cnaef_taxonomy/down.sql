@@ -0,0 +1,31 @@
+CREATE TEMP TABLE saved_course_areas AS
+SELECT guide_year, institution, course, cnaef_area FROM course_areas;
+DROP TABLE course_areas;
+
+CREATE TABLE cnaef_areas_old (
+    code TEXT NOT NULL UNIQUE,
+    name TEXT,
+    PRIMARY KEY(code)
+);
+
+/* Only the ones courses are in, like before the taxonomy */
+INSERT INTO cnaef_areas_old (code, name)
+SELECT code, name FROM cnaef_areas WHERE code IN (SELECT cnaef_area FROM saved_course_areas);
+
+DROP TABLE cnaef_areas;
+ALTER TABLE cnaef_areas_old RENAME TO cnaef_areas;
+
+CREATE TABLE course_areas (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    cnaef_area TEXT NOT NULL,
+    PRIMARY KEY(guide_year, institution, course),
+    FOREIGN KEY(cnaef_area) REFERENCES cnaef_areas(code)
+);
+
+INSERT INTO course_areas (guide_year, institution, course, cnaef_area)
+SELECT guide_year, institution, course, cnaef_area FROM saved_course_areas;
+
+DROP TABLE saved_course_areas;
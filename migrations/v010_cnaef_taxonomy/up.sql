@@ -0,0 +1,184 @@
+/* Nothing can point at cnaef_areas while it's rebuilt */
+CREATE TEMP TABLE saved_course_areas AS SELECT * FROM course_areas;
+DROP TABLE course_areas;
+
+/*
+ * CNAEF areas are a broad group (1 digit), a narrow field (2 digits) and a detailed field
+ * (3 digits), each under the one its code starts with. Pages only give the detailed field.
+ */
+CREATE TABLE cnaef_areas_new (
+    code TEXT NOT NULL UNIQUE,
+    name TEXT,
+    level INTEGER NOT NULL, /* 1, 2, 3 */
+    parent TEXT,
+    PRIMARY KEY(code),
+    FOREIGN KEY(parent) REFERENCES cnaef_areas_new(code)
+);
+
+/* Classificação Nacional das Áreas de Educação e Formação, Portaria n.º 256/2005 */
+INSERT INTO cnaef_areas_new (code, name, level, parent) VALUES
+    ('0', 'Programas gerais', 1, NULL),
+    ('01', 'Programas de base', 2, '0'),
+    ('010', 'Programas de base', 3, '01'),
+    ('08', 'Alfabetização', 2, '0'),
+    ('080', 'Alfabetização', 3, '08'),
+    ('09', 'Desenvolvimento pessoal', 2, '0'),
+    ('090', 'Desenvolvimento pessoal', 3, '09'),
+    ('1', 'Educação', 1, NULL),
+    ('14', 'Formação de professores/formadores e ciências da educação', 2, '1'),
+    ('140', 'Formação de professores/formadores e ciências da educação', 3, '14'),
+    ('141', 'Formação de professores e formadores de áreas de formação específicas', 3, '14'),
+    ('142', 'Ciências da educação', 3, '14'),
+    ('143', 'Formação de educadores de infância', 3, '14'),
+    ('144', 'Formação de professores do ensino básico (1º e 2º ciclos)', 3, '14'),
+    ('145', 'Formação de professores de áreas disciplinares específicas', 3, '14'),
+    ('146', 'Formação de professores e formadores de áreas tecnológicas', 3, '14'),
+    ('2', 'Artes e humanidades', 1, NULL),
+    ('21', 'Artes', 2, '2'),
+    ('210', 'Artes', 3, '21'),
+    ('211', 'Belas-artes', 3, '21'),
+    ('212', 'Artes do espectáculo', 3, '21'),
+    ('213', 'Audiovisuais e produção dos media', 3, '21'),
+    ('214', 'Design', 3, '21'),
+    ('215', 'Artesanato', 3, '21'),
+    ('22', 'Humanidades', 2, '2'),
+    ('220', 'Humanidades', 3, '22'),
+    ('221', 'Religião e teologia', 3, '22'),
+    ('222', 'Línguas e literaturas estrangeiras', 3, '22'),
+    ('223', 'Língua e literatura materna', 3, '22'),
+    ('225', 'História e arqueologia', 3, '22'),
+    ('226', 'Filosofia e ética', 3, '22'),
+    ('3', 'Ciências sociais, comércio e direito', 1, NULL),
+    ('31', 'Ciências sociais e do comportamento', 2, '3'),
+    ('310', 'Ciências sociais e do comportamento', 3, '31'),
+    ('311', 'Psicologia', 3, '31'),
+    ('312', 'Sociologia e outros estudos', 3, '31'),
+    ('313', 'Ciência política e cidadania', 3, '31'),
+    ('314', 'Economia', 3, '31'),
+    ('32', 'Informação e jornalismo', 2, '3'),
+    ('320', 'Informação e jornalismo', 3, '32'),
+    ('321', 'Jornalismo e reportagem', 3, '32'),
+    ('322', 'Biblioteconomia, arquivo e documentação', 3, '32'),
+    ('34', 'Ciências empresariais', 2, '3'),
+    ('340', 'Ciências empresariais', 3, '34'),
+    ('341', 'Comércio', 3, '34'),
+    ('342', 'Marketing e publicidade', 3, '34'),
+    ('343', 'Finanças, banca e seguros', 3, '34'),
+    ('344', 'Contabilidade e fiscalidade', 3, '34'),
+    ('345', 'Gestão e administração', 3, '34'),
+    ('346', 'Secretariado e trabalho administrativo', 3, '34'),
+    ('347', 'Enquadramento na organização/empresa', 3, '34'),
+    ('38', 'Direito', 2, '3'),
+    ('380', 'Direito', 3, '38'),
+    ('4', 'Ciências, matemática e informática', 1, NULL),
+    ('42', 'Ciências da vida', 2, '4'),
+    ('420', 'Ciências da vida', 3, '42'),
+    ('421', 'Biologia e bioquímica', 3, '42'),
+    ('422', 'Ciências do ambiente', 3, '42'),
+    ('44', 'Ciências físicas', 2, '4'),
+    ('440', 'Ciências físicas', 3, '44'),
+    ('441', 'Física', 3, '44'),
+    ('442', 'Química', 3, '44'),
+    ('443', 'Ciências da terra', 3, '44'),
+    ('46', 'Matemática e estatística', 2, '4'),
+    ('460', 'Matemática e estatística', 3, '46'),
+    ('461', 'Matemática', 3, '46'),
+    ('462', 'Estatística', 3, '46'),
+    ('48', 'Informática', 2, '4'),
+    ('480', 'Informática', 3, '48'),
+    ('481', 'Ciências informáticas', 3, '48'),
+    ('482', 'Informática na óptica do utilizador', 3, '48'),
+    ('5', 'Engenharia, indústrias transformadoras e construção', 1, NULL),
+    ('52', 'Engenharia e técnicas afins', 2, '5'),
+    ('520', 'Engenharia e técnicas afins', 3, '52'),
+    ('521', 'Metalurgia e metalomecânica', 3, '52'),
+    ('522', 'Electricidade e energia', 3, '52'),
+    ('523', 'Electrónica e automação', 3, '52'),
+    ('524', 'Tecnologia dos processos químicos', 3, '52'),
+    ('525', 'Construção e reparação de veículos a motor', 3, '52'),
+    ('54', 'Indústrias transformadoras', 2, '5'),
+    ('540', 'Indústrias transformadoras', 3, '54'),
+    ('541', 'Indústrias alimentares', 3, '54'),
+    ('542', 'Indústrias do têxtil, vestuário, calçado e couro', 3, '54'),
+    ('543', 'Materiais (indústrias da madeira, cortiça, papel, plástico, vidro e outros)', 3, '54'),
+    ('544', 'Indústrias extractivas', 3, '54'),
+    ('58', 'Arquitectura e construção', 2, '5'),
+    ('580', 'Arquitectura e construção', 3, '58'),
+    ('581', 'Arquitectura e urbanismo', 3, '58'),
+    ('582', 'Construção civil e engenharia civil', 3, '58'),
+    ('6', 'Agricultura', 1, NULL),
+    ('62', 'Agricultura, silvicultura e pescas', 2, '6'),
+    ('620', 'Agricultura, silvicultura e pescas', 3, '62'),
+    ('621', 'Produção agrícola e animal', 3, '62'),
+    ('622', 'Floricultura e jardinagem', 3, '62'),
+    ('623', 'Silvicultura e caça', 3, '62'),
+    ('624', 'Pescas', 3, '62'),
+    ('64', 'Ciências veterinárias', 2, '6'),
+    ('640', 'Ciências veterinárias', 3, '64'),
+    ('7', 'Saúde e protecção social', 1, NULL),
+    ('72', 'Saúde', 2, '7'),
+    ('720', 'Saúde', 3, '72'),
+    ('721', 'Medicina', 3, '72'),
+    ('723', 'Enfermagem', 3, '72'),
+    ('724', 'Ciências dentárias', 3, '72'),
+    ('725', 'Tecnologias de diagnóstico e terapêutica', 3, '72'),
+    ('726', 'Terapia e reabilitação', 3, '72'),
+    ('727', 'Ciências farmacêuticas', 3, '72'),
+    ('76', 'Serviços sociais', 2, '7'),
+    ('760', 'Serviços sociais', 3, '76'),
+    ('761', 'Serviços de apoio a crianças e jovens', 3, '76'),
+    ('762', 'Trabalho social e orientação', 3, '76'),
+    ('8', 'Serviços', 1, NULL),
+    ('81', 'Serviços pessoais', 2, '8'),
+    ('810', 'Serviços pessoais', 3, '81'),
+    ('811', 'Hotelaria e restauração', 3, '81'),
+    ('812', 'Turismo e lazer', 3, '81'),
+    ('813', 'Desporto', 3, '81'),
+    ('814', 'Serviços domésticos', 3, '81'),
+    ('815', 'Cuidados de beleza', 3, '81'),
+    ('84', 'Serviços de transporte', 2, '8'),
+    ('840', 'Serviços de transporte', 3, '84'),
+    ('85', 'Protecção do ambiente', 2, '8'),
+    ('850', 'Protecção do ambiente', 3, '85'),
+    ('851', 'Tecnologia de protecção do ambiente', 3, '85'),
+    ('852', 'Ambientes naturais e vida selvagem', 3, '85'),
+    ('853', 'Serviços de saúde pública', 3, '85'),
+    ('86', 'Serviços de segurança', 2, '8'),
+    ('860', 'Serviços de segurança', 3, '86'),
+    ('861', 'Protecção de pessoas e bens', 3, '86'),
+    ('862', 'Segurança e higiene no trabalho', 3, '86'),
+    ('863', 'Segurança militar', 3, '86'),
+    ('9', 'Desconhecido', 1, NULL),
+    ('99', 'Desconhecido', 2, '9'),
+    ('999', 'Desconhecido', 3, '99');
+
+/* Areas already saved from pages that the taxonomy doesn't have */
+INSERT OR IGNORE INTO cnaef_areas_new (code, name, level, parent)
+SELECT code, name, length(code), CASE WHEN length(code) > 1 THEN substr(code, 1, length(code) - 1) END
+FROM cnaef_areas
+WHERE length(code) BETWEEN 1 AND 3 AND code NOT GLOB '*[^0-9]*';
+
+DROP TABLE cnaef_areas;
+ALTER TABLE cnaef_areas_new RENAME TO cnaef_areas;
+
+CREATE TABLE course_areas (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    broad_group TEXT NOT NULL, /* 4 */
+    narrow_field TEXT, /* 48 */
+    cnaef_area TEXT NOT NULL, /* 481, as given by the page */
+    PRIMARY KEY(guide_year, institution, course),
+    FOREIGN KEY(broad_group) REFERENCES cnaef_areas(code),
+    FOREIGN KEY(narrow_field) REFERENCES cnaef_areas(code),
+    FOREIGN KEY(cnaef_area) REFERENCES cnaef_areas(code)
+);
+
+INSERT INTO course_areas (guide_year, institution, course, broad_group, narrow_field, cnaef_area)
+SELECT guide_year, institution, course, substr(cnaef_area, 1, 1),
+    CASE WHEN length(cnaef_area) > 1 THEN substr(cnaef_area, 1, 2) END, cnaef_area
+FROM saved_course_areas
+WHERE cnaef_area IN (SELECT code FROM cnaef_areas);
+
+DROP TABLE saved_course_areas;
@@ -0,0 +1,2 @@
+ALTER TABLE runs DROP COLUMN partial;
+ALTER TABLE runs DROP COLUMN guide_year;
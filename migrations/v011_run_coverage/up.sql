@@ -0,0 +1,6 @@
+/*
+ * Which guide a run crawled and whether it saw every course of it. Only then do the courses it
+ * didn't see count as gone. Runs that never finished stay partial.
+ */
+ALTER TABLE runs ADD COLUMN guide_year INTEGER;
+ALTER TABLE runs ADD COLUMN partial BOOLEAN NOT NULL DEFAULT 1;
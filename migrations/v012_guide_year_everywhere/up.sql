@@ -0,0 +1,91 @@
+/*
+ * Institutions, courses, their ECTS, durations and mandatory exams are kept per guide year too,
+ * so a crawl of another guide doesn't overwrite them. What was saved before doesn't say which
+ * guide it came from, so it's dropped and the next crawl saves it again.
+ */
+DROP VIEW expanded_course_institution;
+
+/* whatever points at another table goes first */
+DROP TABLE mandatory_exams;
+DROP TABLE durations;
+DROP TABLE course_institution;
+DROP TABLE courses;
+DROP TABLE institutions;
+
+CREATE TABLE institutions (
+    guide_year INTEGER NOT NULL,
+    code TEXT NOT NULL,
+    /**/
+    name TEXT,
+    /* should be an array of lines - abstract as table */
+    address TEXT,
+    /* should be an array of numbers - abstract as table */
+    phone_numbers TEXT,
+    /* should be an array of email addresses - abstract as table */
+    email_addresses TEXT,
+    PRIMARY KEY(guide_year, code)
+);
+
+CREATE TABLE courses (
+    guide_year INTEGER NOT NULL,
+    code TEXT NOT NULL,
+    /**/
+    name TEXT,
+    PRIMARY KEY(guide_year, code)
+);
+
+/* Not every course says how long it is, so a course doesn't need a duration anymore */
+CREATE TABLE course_institution (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    ects INTEGER,
+    PRIMARY KEY(guide_year, institution, course),
+    FOREIGN KEY(guide_year, institution) REFERENCES institutions(guide_year, code),
+    FOREIGN KEY(guide_year, course) REFERENCES courses(guide_year, code)
+);
+
+CREATE TABLE durations (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    unit TEXT,
+    ammount INTEGER,
+    PRIMARY KEY(guide_year, institution, course),
+    FOREIGN KEY(guide_year, institution, course) REFERENCES course_institution(guide_year, institution, course),
+    FOREIGN KEY(unit) REFERENCES duration_units(name)
+);
+
+CREATE TABLE mandatory_exams (
+    guide_year INTEGER NOT NULL,
+    institution TEXT NOT NULL,
+    course TEXT NOT NULL,
+    /**/
+    exam TEXT NOT NULL,
+    PRIMARY KEY(guide_year, institution, course, exam),
+    FOREIGN KEY(exam) REFERENCES exams(code),
+    FOREIGN KEY(guide_year, institution, course) REFERENCES course_institution(guide_year, institution, course)
+);
+
+CREATE VIEW expanded_course_institution AS
+SELECT course_institution.guide_year,
+course_institution.ects,
+institutions.code as institution_code,
+institutions.name as institution_name,
+courses.code as course_code,
+courses.name as course_name,
+
+durations.ammount as duration_ammount,
+
+durations.unit as duration_unit
+
+
+FROM course_institution
+INNER JOIN institutions
+ON (institutions.guide_year, institutions.code) = (course_institution.guide_year, course_institution.institution)
+INNER JOIN courses
+ON (courses.guide_year, courses.code) = (course_institution.guide_year, course_institution.course)
+LEFT JOIN durations
+ON (durations.guide_year, durations.institution, durations.course) = (course_institution.guide_year, course_institution.institution, course_institution.course);
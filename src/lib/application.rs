@@ -0,0 +1,166 @@
+//! The application grade a candidate would get at each course they can apply to, against how the
+//! last placed candidates did in past years.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use super::course_url::CourseUrl;
+use super::db::{get_formulas, get_statistics};
+use super::eligibility::{course_eligibility, Eligibility};
+use super::exams::{ExamCode, ExamRequirement};
+use super::grades::{Formula, Grade};
+use super::profile::CandidateProfile;
+
+pub struct ApplicationGrade {
+    pub(crate) course: CourseUrl,
+    /// With the combination of exams that gives it. `None` without a secondary average or a
+    /// formula
+    pub(crate) grade: Option<(Grade, BTreeSet<ExamCode>)>,
+    pub(crate) minimum: Option<Grade>,
+    /// Year and grade of the last candidate placed in the most recent first phase
+    pub(crate) last_placed: Option<(u16, Grade)>,
+}
+
+impl ApplicationGrade {
+    /// Points above the last placed candidate, negative if below
+    pub fn margin(&self) -> Option<f64> {
+        match (&self.grade, self.last_placed) {
+            (Some((grade, _)), Some((_, last_placed))) => {
+                Some(grade.points() - last_placed.points())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The best grade any of `combinations` gets the candidate, and the combination
+pub(crate) fn best_application_grade(
+    formula: &Formula,
+    combinations: &[BTreeSet<ExamCode>],
+    profile: &CandidateProfile,
+) -> Option<(Grade, BTreeSet<ExamCode>)> {
+    let secondary_average = profile.secondary_average?;
+    combinations
+        .iter()
+        .filter_map(|combination| {
+            let grades: Option<Vec<Grade>> =
+                combination.iter().map(|exam| profile.grade(exam)).collect();
+            let grade = formula.application_grade(secondary_average, &grades?)?;
+            Some((grade, combination.clone()))
+        })
+        .max_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+}
+
+/// How each course of a guide calculates its application grade
+pub(crate) fn formulas(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+) -> Result<HashMap<CourseUrl, Formula>> {
+    Ok(get_formulas(conn, guide_year)?
+        .into_iter()
+        .map(|row| {
+            let formula = Formula {
+                secondary_average: row
+                    .secondary_average
+                    .and_then(|weight| weight.try_into().ok()),
+                exams: row.exams.and_then(|weight| weight.try_into().ok()),
+            };
+            (CourseUrl::new(&row.institution, &row.course), formula)
+        })
+        .collect())
+}
+
+/// Every course the candidate can apply to, the ones they'd get in by the widest margin first
+pub fn application_grades(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    profile: &CandidateProfile,
+) -> Result<Vec<ApplicationGrade>> {
+    let formulas = formulas(conn, guide_year)?;
+
+    // the rows come newest year first, so the first one of a course is the most recent
+    let mut last_placed: HashMap<CourseUrl, (u16, Grade)> = HashMap::new();
+    for row in get_statistics(conn)? {
+        let grade: Option<Grade> = row
+            .last_placed_grade
+            .and_then(|points| points.try_into().ok());
+        if let (1, Some(grade)) = (row.phase, grade) {
+            last_placed
+                .entry(CourseUrl::new(&row.institution, &row.course))
+                .or_insert((row.year as u16, grade));
+        }
+    }
+
+    let mut grades: Vec<ApplicationGrade> = course_eligibility(conn, guide_year, profile)?
+        .into_iter()
+        .filter_map(|course| match course.eligibility {
+            Eligibility::Eligible(combinations) => {
+                let formula = formulas.get(&course.course).copied().unwrap_or_default();
+                Some(ApplicationGrade {
+                    grade: best_application_grade(&formula, &combinations, profile),
+                    minimum: course.minimum_grades.application,
+                    last_placed: last_placed.get(&course.course).copied(),
+                    course: course.course,
+                })
+            }
+            _ => None,
+        })
+        .collect();
+
+    grades.sort_by(|a, b| {
+        let by_margin = match (a.margin(), b.margin()) {
+            (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        let grade =
+            |grade: &ApplicationGrade| grade.grade.as_ref().map(|(grade, _)| grade.points());
+        by_margin
+            .then_with(|| grade(b).partial_cmp(&grade(a)).unwrap_or(Ordering::Equal))
+            .then_with(|| a.course.cmp(&b.course))
+    });
+    Ok(grades)
+}
+
+/// One course per line, in the order they come in
+pub fn application_grade_table(grades: &[ApplicationGrade]) -> String {
+    let mut table = format!(
+        "{:<10}  {:>6}  {:<20}  {:>7}  {:>13}  {:>7}\n",
+        "course", "grade", "exams", "minimum", "last placed", "margin"
+    );
+    for grade in grades {
+        let (points, exams) = match &grade.grade {
+            Some((points, exams)) => (
+                points.to_string(),
+                ExamRequirement::from_combinations(vec![exams.clone()]).to_string(),
+            ),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let minimum = match grade.minimum {
+            Some(minimum) => minimum.to_string(),
+            None => "-".to_string(),
+        };
+        let last_placed = match grade.last_placed {
+            Some((year, last_placed)) => format!("{} ({})", last_placed, year),
+            None => "-".to_string(),
+        };
+        let margin = match grade.margin() {
+            Some(margin) => format!("{:+.1}", margin),
+            None => "-".to_string(),
+        };
+        table.push_str(&format!(
+            "{:<10}  {:>6}  {:<20}  {:>7}  {:>13}  {:>7}\n",
+            grade.course.to_string(),
+            points,
+            exams,
+            minimum,
+            last_placed,
+            margin
+        ));
+    }
+    table
+}
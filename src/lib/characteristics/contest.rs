@@ -1,8 +1,39 @@
-#[derive(Debug, Default)]
-pub(crate) struct Contest(String);
+use std::fmt::Display;
+
+/// "Concurso"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Contest {
+    /// `Nacional`
+    National,
+    /// `Local`
+    Local,
+    /// Anything else, as found on the page
+    Other(String),
+}
+
+impl Contest {
+    pub(crate) fn is_other(&self) -> bool {
+        matches!(self, Contest::Other(_))
+    }
+}
 
 impl From<&str> for Contest {
     fn from(value: &str) -> Self {
-        Contest(value.into())
+        match value.trim() {
+            "Nacional" => Contest::National,
+            "Local" => Contest::Local,
+            other => Contest::Other(other.to_string()),
+        }
+    }
+}
+
+/// As written on the page
+impl Display for Contest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Contest::National => write!(f, "Nacional"),
+            Contest::Local => write!(f, "Local"),
+            Contest::Other(other) => write!(f, "{}", other),
+        }
     }
-}
\ No newline at end of file
+}
@@ -13,7 +13,7 @@ impl From<&str> for Code {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Name(String);
 
 impl From<&str> for Name {
@@ -26,4 +26,10 @@ impl From<Code> for String {
     fn from(val: Code) -> Self {
         val.0
     }
-}
\ No newline at end of file
+}
+
+impl From<Name> for String {
+    fn from(val: Name) -> Self {
+        val.0
+    }
+}
@@ -1,8 +1,41 @@
-#[derive(Debug, Default)]
-pub(crate) struct Degree(String);
+use std::fmt::Display;
+
+/// "Grau"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Degree {
+    /// `Licenciatura - 1º ciclo`
+    Licenciatura,
+    /// `Mestrado Integrado`
+    MestradoIntegrado,
+    /// Anything else, as found on the page
+    Other(String),
+}
+
+impl Degree {
+    pub(crate) fn is_other(&self) -> bool {
+        matches!(self, Degree::Other(_))
+    }
+}
 
 impl From<&str> for Degree {
     fn from(value: &str) -> Self {
-        Degree(value.into())
+        match value.trim() {
+            "Licenciatura - 1º ciclo" | "Licenciatura 1º ciclo" | "Licenciatura" => {
+                Degree::Licenciatura
+            }
+            "Mestrado Integrado" => Degree::MestradoIntegrado,
+            other => Degree::Other(other.to_string()),
+        }
+    }
+}
+
+/// As written on the page
+impl Display for Degree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Degree::Licenciatura => write!(f, "Licenciatura - 1º ciclo"),
+            Degree::MestradoIntegrado => write!(f, "Mestrado Integrado"),
+            Degree::Other(other) => write!(f, "{}", other),
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,25 +1,76 @@
+use std::fmt::Display;
+
 #[derive(Debug, Default)]
 pub(crate) struct Duration {
     pub(crate) ammount: Option<Ammount>,
     pub(crate) unit: Option<Unit>,
 }
 
-#[derive(Debug, Default)]
-pub(crate) struct Unit(String);
+impl Duration {
+    /// `None` without an ammount, or with a unit that isn't a known length of time
+    pub(crate) fn months(&self) -> Option<u16> {
+        let ammount = u16::from(u8::from(self.ammount?));
+        Some(ammount * self.unit.as_ref()?.months()?)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Unit {
+    /// `Semestres`
+    Semesters,
+    /// `Anos`
+    Years,
+    /// `Trimestres`
+    Trimesters,
+    /// Anything else, as found on the page
+    Other(String),
+}
+
+impl Unit {
+    pub(crate) fn is_other(&self) -> bool {
+        matches!(self, Unit::Other(_))
+    }
+
+    pub(crate) fn months(&self) -> Option<u16> {
+        match self {
+            Unit::Semesters => Some(6),
+            Unit::Years => Some(12),
+            Unit::Trimesters => Some(3),
+            Unit::Other(_) => None,
+        }
+    }
+}
 
 impl From<&str> for Unit {
     fn from(value: &str) -> Self {
-        Unit(value.into())
+        match value.trim() {
+            "Semestres" | "Semestre" => Unit::Semesters,
+            "Anos" | "Ano" => Unit::Years,
+            "Trimestres" | "Trimestre" => Unit::Trimesters,
+            other => Unit::Other(other.to_string()),
+        }
+    }
+}
+
+/// As written on the page
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Semesters => write!(f, "Semestres"),
+            Unit::Years => write!(f, "Anos"),
+            Unit::Trimesters => write!(f, "Trimestres"),
+            Unit::Other(other) => write!(f, "{}", other),
+        }
     }
 }
 
 impl From<Unit> for String {
     fn from(value: Unit) -> Self {
-        value.0
+        value.to_string()
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub(crate) struct Ammount(u8);
 
 impl From<u8> for Ammount {
@@ -1,8 +1,39 @@
-#[derive(Debug, Default)]
-pub(crate) struct EducationType(String);
+use std::fmt::Display;
+
+/// "Tipo de Ensino"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EducationType {
+    /// `Universitário`
+    University,
+    /// `Politécnico`
+    Polytechnic,
+    /// Anything else, as found on the page
+    Other(String),
+}
+
+impl EducationType {
+    pub(crate) fn is_other(&self) -> bool {
+        matches!(self, EducationType::Other(_))
+    }
+}
 
 impl From<&str> for EducationType {
     fn from(value: &str) -> Self {
-        EducationType(value.into())
+        match value.trim() {
+            "Universitário" => EducationType::University,
+            "Politécnico" => EducationType::Polytechnic,
+            other => EducationType::Other(other.to_string()),
+        }
+    }
+}
+
+/// As written on the page
+impl Display for EducationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EducationType::University => write!(f, "Universitário"),
+            EducationType::Polytechnic => write!(f, "Politécnico"),
+            EducationType::Other(other) => write!(f, "{}", other),
+        }
     }
-}
\ No newline at end of file
+}
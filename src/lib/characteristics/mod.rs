@@ -1,7 +1,7 @@
 pub(crate) use contest::Contest;
 pub(crate) use course::Course;
 pub(crate) use degree::Degree;
-pub(crate) use duration::Duration;
+pub(crate) use duration::{Duration, Unit};
 pub(crate) use ects::Ects;
 pub(crate) use education_type::EducationType;
 use ego_tree::NodeRef;
@@ -19,6 +19,9 @@ pub mod institution;
 use anyhow::{bail, Result};
 use cnaef_area::CnaefArea;
 
+use super::diagnostics::ParseDiagnostic;
+use super::unrecognized::UnrecognizedContent;
+
 #[derive(Debug, Default)]
 pub(crate) struct Characteristics {
     pub(crate) course: Course,
@@ -55,50 +58,24 @@ impl Characteristics {
 
 pub(crate) fn characteristics_section<'a>(
     it: &mut impl Iterator<Item = NodeRef<'a, Node>>,
+    unrecognized: &mut Vec<UnrecognizedContent>,
+    diagnostics: &mut Vec<ParseDiagnostic>,
 ) -> Characteristics {
     let mut characteristics = Characteristics::default();
 
     while let Some(sibling) = it.next() {
         if let Some(text) = sibling.value().as_text() {
             if let Some((field, value)) = text.split_once(": ") {
-                /*
-                TODO: the errors that are being ignored below
-                TODO: should, maybe be stored somewhere, or logged at least
-                */
-                match field {
-                    "Código" => {
-                        if let Ok((institution_code, course_code)) = parse_code(value) {
-                            characteristics.institution.code = Some(institution_code);
-                            characteristics.course.code = Some(course_code);
-                        }
-                    }
-                    "Grau" => {
-                        characteristics.degree = parse_degree(value).ok();
-                    }
-                    "Área CNAEF" => {
-                        if let Ok(cnaef_area) = parse_cnaef_area(value) {
-                            characteristics.cnaef_area = cnaef_area
-                        }
-                    }
-                    "Duração" => {
-                        if let Ok(duration) = parse_duration(value) {
-                            characteristics.duration = duration
-                        }
-                    }
-                    "ECTS" => {
-                        characteristics.ects = parse_ects(value).ok();
-                    }
-                    "Tipo de Ensino" => {
-                        characteristics.education_type = parse_education_type(value).ok();
-                    }
-                    "Concurso" => {
-                        characteristics.contest = parse_contest(value).ok();
-                    }
-                    field => {
-                        //TODO: This should store unkown fields somewhere
+                match parse_field(&mut characteristics, field, value, diagnostics) {
+                    Ok(true) => {}
+                    Ok(false) => {
                         info!("UNKNOWN FIELD: {}", field);
+                        unrecognized.push(UnrecognizedContent::field(sibling, field, value));
                     }
-                };
+                    Err(err) => {
+                        diagnostics.push(ParseDiagnostic::new("characteristics", field, value, err))
+                    }
+                }
             } else {
                 break;
             }
@@ -111,6 +88,57 @@ pub(crate) fn characteristics_section<'a>(
     characteristics
 }
 
+/// Fills in one `Field: value` line. `Ok(false)` if it's not a field we know. Values a field
+/// doesn't usually have are kept, and reported in `diagnostics`
+fn parse_field(
+    characteristics: &mut Characteristics,
+    field: &str,
+    value: &str,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Result<bool> {
+    let mut unknown_value = |is_other: bool| {
+        if is_other {
+            diagnostics.push(ParseDiagnostic::new(
+                "characteristics",
+                field,
+                value,
+                "not a known value",
+            ));
+        }
+    };
+    match field {
+        "Código" => {
+            let (institution_code, course_code) = parse_code(value)?;
+            characteristics.institution.code = Some(institution_code);
+            characteristics.course.code = Some(course_code);
+        }
+        "Grau" => {
+            let degree = parse_degree(value)?;
+            unknown_value(degree.is_other());
+            characteristics.degree = Some(degree);
+        }
+        "Área CNAEF" => characteristics.cnaef_area = parse_cnaef_area(value)?,
+        "Duração" => {
+            let duration = parse_duration(value)?;
+            unknown_value(duration.unit.as_ref().map_or(false, Unit::is_other));
+            characteristics.duration = duration;
+        }
+        "ECTS" => characteristics.ects = Some(parse_ects(value)?),
+        "Tipo de Ensino" => {
+            let education_type = parse_education_type(value)?;
+            unknown_value(education_type.is_other());
+            characteristics.education_type = Some(education_type);
+        }
+        "Concurso" => {
+            let contest = parse_contest(value)?;
+            unknown_value(contest.is_other());
+            characteristics.contest = Some(contest);
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
 fn parse_code(value: &str) -> Result<(institution::Code, course::Code)> {
     let split_value = value.split_once("/");
     if let Some((institution_code, course_code)) = split_value {
@@ -135,7 +163,15 @@ fn parse_degree(value: &str) -> Result<Degree> {
     Ok(value.into())
 }
 
+/// `6 Semestres`, or just `6` if the page leaves the unit out
 fn parse_duration(value: &str) -> Result<Duration> {
+    if let Ok(duration_ammount) = value.trim().parse::<u8>() {
+        let ammount = Some(duration_ammount.into());
+        return Ok(Duration {
+            ammount,
+            unit: None,
+        });
+    }
     let split_value = value.split_once(" ");
     match split_value {
         Some((duration_ammount, duration_unit)) => match duration_ammount.parse::<u8>() {
@@ -0,0 +1,181 @@
+//! CNAEF areas are hierarchical: a broad group (`4` Ciências, matemática e informática), narrow
+//! fields under it (`48` Informática) and detailed fields under those (`481` Ciências
+//! informáticas). The migrations load the whole taxonomy, pages only give the detailed field.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{bail, Result};
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use super::course_url::CourseUrl;
+use super::db::models::CnaefArea;
+use super::db::{get_cnaef_areas, get_course_areas, get_course_areas_under};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    /// 1 digit
+    BroadGroup,
+    /// 2 digits
+    NarrowField,
+    /// 3 digits
+    DetailedField,
+}
+
+impl Level {
+    /// `None` for anything that isn't 1 to 3 digits
+    pub fn of(code: &str) -> Option<Level> {
+        if !code.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        match code.len() {
+            1 => Some(Level::BroadGroup),
+            2 => Some(Level::NarrowField),
+            3 => Some(Level::DetailedField),
+            _ => None,
+        }
+    }
+
+    pub fn digits(&self) -> usize {
+        match self {
+            Level::BroadGroup => 1,
+            Level::NarrowField => 2,
+            Level::DetailedField => 3,
+        }
+    }
+}
+
+impl From<Level> for i32 {
+    fn from(level: Level) -> Self {
+        level.digits() as i32
+    }
+}
+
+/// The area `code` is under at `level`, `48` for `481` at the narrow field level. `None` if
+/// `code` is at a broader level than that
+pub(crate) fn area_at(code: &str, level: Level) -> Option<&str> {
+    if Level::of(code)? < level {
+        None
+    } else {
+        code.get(..level.digits())
+    }
+}
+
+/// The area right above `code`, `None` for broad groups
+pub(crate) fn parent(code: &str) -> Option<&str> {
+    match Level::of(code)? {
+        Level::BroadGroup => None,
+        level => code.get(..level.digits() - 1),
+    }
+}
+
+/// An area with every course of a guide under it, at any level below
+#[derive(Debug, Clone, Serialize)]
+pub struct AreaNode {
+    pub code: String,
+    pub name: Option<String>,
+    pub level: Level,
+    /// `institution/course`
+    pub courses: Vec<String>,
+    pub children: Vec<AreaNode>,
+}
+
+/// The taxonomy as a tree of broad groups, with the courses of a guide. Only `under` and the
+/// areas below it, if given
+pub fn area_tree(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    under: Option<&str>,
+) -> Result<Vec<AreaNode>> {
+    let rows = match under {
+        Some(area) => get_course_areas_under(conn, guide_year, area)?,
+        None => get_course_areas(conn, guide_year)?,
+    };
+    let mut courses: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        courses
+            .entry(row.cnaef_area)
+            .or_default()
+            .push(CourseUrl::new(&row.institution, &row.course).to_string());
+    }
+
+    let mut areas: BTreeMap<Option<String>, Vec<CnaefArea>> = BTreeMap::new();
+    let mut root = None;
+    for area in get_cnaef_areas(conn)? {
+        if Some(area.code.as_str()) == under {
+            root = Some(area);
+        } else {
+            areas.entry(area.parent.clone()).or_default().push(area);
+        }
+    }
+
+    let roots = match (under, root) {
+        (Some(_), Some(root)) => vec![root],
+        (Some(under), None) => bail!("No CNAEF area {}", under),
+        (None, _) => areas.remove(&None).unwrap_or_default(),
+    };
+    Ok(roots
+        .into_iter()
+        .filter_map(|area| node(area, &mut areas, &mut courses))
+        .collect())
+}
+
+fn node(
+    area: CnaefArea,
+    areas: &mut BTreeMap<Option<String>, Vec<CnaefArea>>,
+    courses: &mut HashMap<String, Vec<String>>,
+) -> Option<AreaNode> {
+    let level = Level::of(&area.code)?;
+    let children: Vec<AreaNode> = areas
+        .remove(&Some(area.code.clone()))
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|child| node(child, areas, courses))
+        .collect();
+    let mut under = courses.remove(&area.code).unwrap_or_default();
+    for child in children.iter() {
+        under.extend(child.courses.iter().cloned());
+    }
+    under.sort();
+    Some(AreaNode {
+        code: area.code,
+        name: area.name,
+        level,
+        courses: under,
+        children,
+    })
+}
+
+/// An area per line, indented under the one above it, with how many courses it has and, if
+/// `list` is set, which ones the detailed fields have. Areas with no courses are left out
+pub fn area_report(tree: &[AreaNode], list: bool) -> String {
+    let mut report = String::new();
+    for area in tree {
+        write_area(&mut report, area, list);
+    }
+    report
+}
+
+fn write_area(report: &mut String, area: &AreaNode, list: bool) {
+    if area.courses.is_empty() {
+        return;
+    }
+    let indent = 2 * (area.level.digits() - 1);
+    report.push_str(&format!(
+        "{:indent$}{:<3}  {:>4}  {}\n",
+        "",
+        area.code,
+        area.courses.len(),
+        area.name.as_deref().unwrap_or("-"),
+        indent = indent
+    ));
+    if list && area.children.is_empty() {
+        for course in area.courses.iter() {
+            report.push_str(&format!("{:indent$}{}\n", "", course, indent = indent + 11));
+        }
+    }
+    for child in area.children.iter() {
+        write_area(report, child, list);
+    }
+}
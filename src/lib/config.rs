@@ -0,0 +1,171 @@
+use std::{path::PathBuf, time::Duration};
+
+use reqwest::{StatusCode, Url};
+use reqwest_retry::policies::ExponentialBackoff;
+
+use super::utils::{cassette_middleware::CassetteMode, throttle_middleware::ThrottleConfig};
+
+pub const DEFAULT_BASE_URL: &str = "https://dges.gov.pt/guias/";
+pub const DEFAULT_INDEX_PATH: &str = "indcurso.asp";
+pub const DEFAULT_COURSE_PATH: &str = "detcursopi.asp";
+
+/// Where the crawler goes looking for pages.
+///
+/// Defaults to the live DGES site, but can point to a mirror or to a local mock server.
+#[derive(Debug, Clone)]
+pub struct SiteConfig {
+    /// Everything else is resolved against this. Should end with a `/`.
+    pub base_url: Url,
+    /// Letter index page (`indcurso.asp`)
+    pub index_path: String,
+    /// Course detail page (`detcursopi.asp`)
+    pub course_path: String,
+    /// Domains the crawler is allowed to visit. If empty, the host of `base_url` is used.
+    pub allowed_domains: Vec<String>,
+    /// Year of the guide the site serves. Pages that say otherwise get a diagnostic, and pages
+    /// that don't say are taken to be from it
+    pub guide_year: Option<u16>,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            base_url: Url::parse(DEFAULT_BASE_URL).unwrap(),
+            index_path: DEFAULT_INDEX_PATH.into(),
+            course_path: DEFAULT_COURSE_PATH.into(),
+            allowed_domains: Vec::new(),
+            guide_year: None,
+        }
+    }
+}
+
+impl SiteConfig {
+    pub fn index_url(&self) -> Url {
+        self.base_url
+            .join(&self.index_path)
+            .expect("index path should be a valid relative url")
+    }
+
+    pub fn course_url(&self, institution_code: &str, course_code: &str) -> Url {
+        let mut url = self
+            .base_url
+            .join(&self.course_path)
+            .expect("course path should be a valid relative url");
+        url.query_pairs_mut()
+            .append_pair("codc", course_code)
+            .append_pair("code", institution_code);
+        url
+    }
+
+    pub fn allowed_domains(&self) -> Vec<String> {
+        if self.allowed_domains.is_empty() {
+            self.base_url
+                .host_str()
+                .map(String::from)
+                .into_iter()
+                .collect()
+        } else {
+            self.allowed_domains.clone()
+        }
+    }
+
+    /// Page name as it shows up in the `href`s of the index pages
+    pub(crate) fn index_page(&self) -> &str {
+        page_name(&self.index_path)
+    }
+
+    /// Page name as it shows up in the `href`s of the index pages
+    pub(crate) fn course_page(&self) -> &str {
+        page_name(&self.course_path)
+    }
+}
+
+fn page_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// How the http client behaves, independently of the site being crawled.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    /// Record responses to, or replay them from, a cassette directory
+    pub cassette: Option<(CassetteMode, PathBuf)>,
+    pub retry: RetryConfig,
+    pub politeness: PolitenessConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Retries after the first attempt. 0 disables retrying
+    pub max_retries: u32,
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    pub backoff_exponent: u32,
+    /// Status codes worth retrying. Connection errors and timeouts are always retried
+    pub retryable_statuses: Vec<StatusCode>,
+}
+
+pub const DEFAULT_RETRYABLE_STATUSES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            min_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(60),
+            backoff_exponent: 2,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES
+                .iter()
+                .filter_map(|status| StatusCode::from_u16(*status).ok())
+                .collect(),
+        }
+    }
+}
+
+impl RetryConfig {
+    pub(crate) fn policy(&self) -> ExponentialBackoff {
+        ExponentialBackoff::builder()
+            .retry_bounds(self.min_interval, self.max_interval.max(self.min_interval))
+            .backoff_exponent(self.backoff_exponent)
+            .build_with_max_retries(self.max_retries)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolitenessConfig {
+    /// Delay between requests when robots.txt doesn't set a `Crawl-delay`
+    pub default_delay: Duration,
+    /// The delay shrinks down to this while the server is healthy, but never below `Crawl-delay`.
+    /// The same as `default_delay` unless asked for, so by default the delay only ever grows
+    pub min_delay: Duration,
+    /// The delay grows up to this while the server is struggling
+    pub max_delay: Duration,
+    pub respect_crawl_delay: bool,
+}
+
+impl Default for PolitenessConfig {
+    fn default() -> Self {
+        Self {
+            default_delay: Duration::from_secs(10),
+            min_delay: Duration::from_secs(10),
+            max_delay: Duration::from_secs(120),
+            respect_crawl_delay: true,
+        }
+    }
+}
+
+impl PolitenessConfig {
+    pub(crate) fn throttle_config(&self, crawl_delay: Option<Duration>) -> ThrottleConfig {
+        match crawl_delay {
+            Some(crawl_delay) => ThrottleConfig {
+                initial: crawl_delay,
+                floor: self.min_delay.max(crawl_delay),
+                ceiling: self.max_delay,
+            },
+            None => ThrottleConfig {
+                initial: self.default_delay,
+                floor: self.min_delay,
+                ceiling: self.max_delay,
+            },
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Display;
+
+use reqwest::Url;
+use thiserror::Error;
+
+/// Canonical key of an institution/course pair, as found in a `detcursopi.asp` url.
+///
+/// Built from the `code` (institution) and `codc` (course) query parameters, so their order
+/// (and any other parameter) doesn't matter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct CourseUrl {
+    institution_code: String,
+    course_code: String,
+}
+
+#[derive(Error, Debug)]
+#[error("not a course url: {0}")]
+pub(crate) struct NotACourseUrl(String);
+
+impl CourseUrl {
+    pub(crate) fn new(institution_code: &str, course_code: &str) -> Self {
+        CourseUrl {
+            institution_code: institution_code.trim().to_string(),
+            course_code: course_code.trim().to_string(),
+        }
+    }
+
+    pub(crate) fn institution_code(&self) -> &str {
+        &self.institution_code
+    }
+
+    pub(crate) fn course_code(&self) -> &str {
+        &self.course_code
+    }
+}
+
+impl TryFrom<&Url> for CourseUrl {
+    type Error = NotACourseUrl;
+
+    fn try_from(url: &Url) -> Result<Self, Self::Error> {
+        let mut institution_code = None;
+        let mut course_code = None;
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "code" => institution_code = Some(value),
+                "codc" => course_code = Some(value),
+                _ => {}
+            }
+        }
+        match (institution_code, course_code) {
+            (Some(institution_code), Some(course_code))
+                if !institution_code.trim().is_empty() && !course_code.trim().is_empty() =>
+            {
+                Ok(CourseUrl::new(&institution_code, &course_code))
+            }
+            _ => Err(NotACourseUrl(url.to_string())),
+        }
+    }
+}
+
+/// Same format as the "Código" field on the course page
+impl Display for CourseUrl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.institution_code, self.course_code)
+    }
+}
+
+/// Where the crawler found a course
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum EntrySource {
+    /// Linked from the index page of this letter
+    LetterIndex(String),
+    /// Row of the source csv file, starting at 1
+    Csv { row: usize },
+    /// Queued again by `retry-failed`
+    RetryFailed,
+}
+
+impl Display for EntrySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntrySource::LetterIndex(letter) => write!(f, "letter {}", letter),
+            EntrySource::Csv { row } => write!(f, "csv row {}", row),
+            EntrySource::RetryFailed => write!(f, "retry"),
+        }
+    }
+}
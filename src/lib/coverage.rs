@@ -0,0 +1,222 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use super::course_url::{CourseUrl, EntrySource};
+use super::diagnostics::ParseDiagnostic;
+use super::workload::Anomaly;
+
+/// What a run found versus what it managed to save.
+///
+/// The scraper fills in the letter pages and the courses it visits, `handle_results` fills in
+/// what came back. Anything that got lost in between shows up in the report.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    /// Course links found on each letter page. `None` if the page itself was never scraped
+    letters: BTreeMap<String, Option<usize>>,
+    /// Courses visited, and where they were found
+    queued: BTreeMap<CourseUrl, EntrySource>,
+    /// Courses that came back as an `Entry`
+    scraped: BTreeSet<CourseUrl>,
+    /// Courses that made it into the database
+    persisted: BTreeSet<CourseUrl>,
+    /// Why a course didn't come back, when we know
+    failures: BTreeMap<CourseUrl, String>,
+    /// Entries that came back with required fields missing
+    incomplete: BTreeMap<CourseUrl, Vec<&'static str>>,
+    /// Courses DGES answered with its "not found" page
+    not_found: BTreeSet<CourseUrl>,
+    /// Values that were on the page but couldn't be parsed
+    diagnostics: BTreeMap<CourseUrl, Vec<String>>,
+    /// Durations and ECTS that don't add up. The page is wrong, not the scraper
+    anomalies: BTreeMap<CourseUrl, Vec<String>>,
+}
+
+pub(super) type SharedCoverage = Arc<Mutex<Coverage>>;
+
+impl Coverage {
+    pub(super) fn letter_found(&mut self, letter: &str) {
+        self.letters.entry(letter.to_string()).or_insert(None);
+    }
+
+    pub(super) fn letter_scraped(&mut self, letter: &str, course_links: usize) {
+        self.letters.insert(letter.to_string(), Some(course_links));
+    }
+
+    pub(super) fn queued(&mut self, course: CourseUrl, source: EntrySource) {
+        self.queued.insert(course, source);
+    }
+
+    pub(super) fn scraped(&mut self, course: CourseUrl, missing_fields: Vec<&'static str>) {
+        if !missing_fields.is_empty() {
+            self.incomplete.insert(course.clone(), missing_fields);
+        }
+        self.scraped.insert(course);
+    }
+
+    pub(super) fn diagnosed(&mut self, course: CourseUrl, diagnostics: &[ParseDiagnostic]) {
+        if !diagnostics.is_empty() {
+            self.diagnostics.insert(
+                course,
+                diagnostics.iter().map(ToString::to_string).collect(),
+            );
+        }
+    }
+
+    pub(super) fn anomalies(&mut self, course: CourseUrl, anomalies: &[Anomaly]) {
+        if !anomalies.is_empty() {
+            self.anomalies
+                .insert(course, anomalies.iter().map(ToString::to_string).collect());
+        }
+    }
+
+    /// Deliberately not saved, so it's neither lost nor missing
+    pub(super) fn filtered_out(&mut self, course: &CourseUrl) {
+        self.queued.remove(course);
+        self.scraped.remove(course);
+        self.incomplete.remove(course);
+        self.diagnostics.remove(course);
+        self.anomalies.remove(course);
+    }
+
+    pub(super) fn persisted(&mut self, course: CourseUrl) {
+        self.failures.remove(&course);
+        self.persisted.insert(course);
+    }
+
+    pub(super) fn not_found(&mut self, course: CourseUrl) {
+        self.not_found.insert(course);
+    }
+
+    pub(super) fn failed(&mut self, course: CourseUrl, error: String) {
+        self.failures.insert(course, error);
+    }
+
+    /// Letters whose page had no course links, or was never scraped
+    fn empty_letters(&self) -> impl Iterator<Item = (&String, &Option<usize>)> {
+        self.letters
+            .iter()
+            .filter(|(_, links)| !matches!(links, Some(links) if *links > 0))
+    }
+
+    /// Courses visited that never came back as an `Entry`
+    fn lost(&self) -> impl Iterator<Item = (&CourseUrl, &EntrySource)> {
+        self.queued.iter().filter(|(course, _)| {
+            !self.scraped.contains(course) && !self.not_found.contains(course)
+        })
+    }
+
+    /// Every letter page was scraped and every course on them came back, so whatever the run
+    /// didn't see isn't on the site
+    pub fn saw_every_course(&self) -> bool {
+        !self.letters.is_empty()
+            && self.empty_letters().next().is_none()
+            && self.lost().next().is_none()
+    }
+
+    /// Nothing missing, nothing lost
+    pub fn is_complete(&self) -> bool {
+        self.empty_letters().next().is_none()
+            && self.lost().next().is_none()
+            && self.not_found.is_empty()
+            && self.diagnostics.is_empty()
+            && self.incomplete.is_empty()
+            && self.scraped.len() == self.persisted.len()
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} letters, {} course links, {} courses visited, {} scraped, {} saved, {} parse diagnostics, {} anomalies",
+            self.letters.len(),
+            self.letters.values().flatten().sum::<usize>(),
+            self.queued.len(),
+            self.scraped.len(),
+            self.persisted.len(),
+            self.diagnostics.values().map(Vec::len).sum::<usize>(),
+            self.anomalies.values().map(Vec::len).sum::<usize>()
+        )
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for Coverage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "# Coverage report")?;
+        writeln!(f)?;
+        writeln!(f, "{}", self.summary())?;
+
+        writeln!(f)?;
+        writeln!(f, "## Letters with no course links")?;
+        for (letter, links) in self.empty_letters() {
+            match links {
+                Some(_) => writeln!(f, "- {}", letter)?,
+                None => writeln!(f, "- {} (page not scraped)", letter)?,
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Courses not found")?;
+        let mut not_found: Vec<_> = self
+            .not_found
+            .iter()
+            .map(|course| (self.queued.get(course), course))
+            .collect();
+        // by input row, so bad codes are easy to find in the source file
+        not_found.sort();
+        for (source, course) in not_found {
+            match source {
+                Some(source) => writeln!(f, "- {}: {}", source, course)?,
+                None => writeln!(f, "- {}", course)?,
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Courses that never produced an entry")?;
+        for (course, source) in self.lost() {
+            match self.failures.get(course) {
+                Some(error) => writeln!(f, "- {} ({}): {}", course, source, error)?,
+                None => writeln!(f, "- {} ({})", course, source)?,
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Entries that weren't saved")?;
+        for course in self.scraped.difference(&self.persisted) {
+            match self.failures.get(course) {
+                Some(error) => writeln!(f, "- {}: {}", course, error)?,
+                None => writeln!(f, "- {}", course)?,
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Entries missing required fields")?;
+        for (course, missing_fields) in self.incomplete.iter() {
+            writeln!(f, "- {}: {}", course, missing_fields.join(", "))?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Parse diagnostics")?;
+        for (course, diagnostics) in self.diagnostics.iter() {
+            for diagnostic in diagnostics {
+                writeln!(f, "- {}: {}", course, diagnostic)?;
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "## Anomalies")?;
+        for (course, anomalies) in self.anomalies.iter() {
+            for anomaly in anomalies {
+                writeln!(f, "- {}: {}", course, anomaly)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,38 +2,59 @@ use diesel::prelude::*;
 use diesel::query_builder::{AsQuery, InsertStatement};
 use diesel::sqlite::{Sqlite, SqliteConnection};
 use dotenv::dotenv;
+use std::env;
 use std::fmt::Display;
-use std::{env, fs};
 use thiserror::Error;
 use tracing::info;
 
 use diesel::result::Error as DieselError;
+use diesel_migrations::MigrationHarness;
 
-mod models;
+use super::MIGRATIONS;
+
+pub(crate) mod models;
 pub(crate) mod schema;
 
 use crate::lib::db::models::{
-    Exam, Institution, NewDuration, NewExam, NewInstitution, NewMain, NewMandatoryExam,
+    Exam, NewCourse, NewCourseInstitution, NewDuration, NewExam, NewInstitution, NewMandatoryExam,
 };
 
-use self::models::{DurationUnit, Main, MandatoryExam, NewCnaefArea, NewDurationUnit};
+use self::models::{
+    Anomaly, CnaefArea, CourseArea, ExamRequirement, FailedPage, Formula, GuideCourse,
+    MinimumGrades, NewAnomaly, NewCnaefArea, NewCourseArea, NewDurationUnit, NewExamRequirement,
+    NewFailedPage, NewFormula, NewGuideCourse, NewMinimumGrades, NewParseDiagnostic, NewRun,
+    NewStatistics, NewUnrecognizedContent, NewWorkload, Run, Statistics, UnrecognizedContent,
+    Workload,
+};
 
+/// Inserts the duration of a course in a guide, or updates it
 pub fn create_duration(
     conn: &mut SqliteConnection,
-    main_id: i32,
-    duration_unit_id: i32,
-    ammount: i32,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    unit: Option<&str>,
+    ammount: Option<i32>,
 ) {
     use schema::durations;
 
     let new_duration = NewDuration {
-        rowid: main_id,
-        unit: duration_unit_id,
+        guide_year,
+        institution,
+        course,
+        unit,
         ammount,
     };
 
     let insert_result = diesel::insert_into(durations::table)
         .values(&new_duration)
+        .on_conflict((
+            durations::guide_year,
+            durations::institution,
+            durations::course,
+        ))
+        .do_update()
+        .set(&new_duration)
         .execute(conn);
 
     if let Err(err) = insert_result {
@@ -41,48 +62,44 @@ pub fn create_duration(
     }
 }
 
-pub fn create_duration_unit<'a>(
-    conn: &mut SqliteConnection,
-    new_name: &'a str,
-) -> Result<DurationUnit, ()> {
+pub fn create_duration_unit(conn: &mut SqliteConnection, name: &str) {
     use schema::duration_units;
-    use schema::duration_units::dsl::*;
 
-    let new_duration_unit = NewDurationUnit { name: new_name };
+    let new_duration_unit = NewDurationUnit { name };
 
-    let result = conn.transaction(|conn| {
-        let mut result = diesel::insert_into(duration_units::table)
-            .values(&new_duration_unit)
-            .get_result::<DurationUnit>(conn);
-        if let Err(ref err) = result {
-            match err {
-                DieselError::DatabaseError(err, _) => match err {
-                    diesel::result::DatabaseErrorKind::UniqueViolation => {
-                        result = duration_units
-                            .filter(name.eq(new_name))
-                            .first::<DurationUnit>(conn);
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
-        }
-        result
-    });
+    let insert_result = diesel::insert_into(duration_units::table)
+        .values(&new_duration_unit)
+        .on_conflict(duration_units::name)
+        .do_nothing()
+        .execute(conn);
 
-    match result {
-        Ok(result) => Ok(result),
-        Err(_) => Err(()),
+    if let Err(err) = insert_result {
+        info!("{}", err);
     }
 }
 
-pub fn create_cnaef_area<'a>(conn: &mut SqliteConnection, code: &'a str, name: &'a str) {
+/// Inserts a CNAEF area the taxonomy loaded by the migrations doesn't have. The names in the
+/// taxonomy are kept
+pub fn create_cnaef_area<'a>(
+    conn: &mut SqliteConnection,
+    code: &'a str,
+    name: &'a str,
+    level: i32,
+    parent: Option<&'a str>,
+) {
     use schema::cnaef_areas;
 
-    let new_cnaef_area = NewCnaefArea { code, name };
+    let new_cnaef_area = NewCnaefArea {
+        code,
+        name,
+        level,
+        parent,
+    };
 
     let insert_result = diesel::insert_into(cnaef_areas::table)
         .values(&new_cnaef_area)
+        .on_conflict(cnaef_areas::code)
+        .do_nothing()
         .execute(conn);
 
     if let Err(err) = insert_result {
@@ -90,6 +107,13 @@ pub fn create_cnaef_area<'a>(conn: &mut SqliteConnection, code: &'a str, name: &
     }
 }
 
+/// Every level of the taxonomy, by code so each area comes right before the ones under it
+pub fn get_cnaef_areas(conn: &mut SqliteConnection) -> Result<Vec<CnaefArea>, DieselError> {
+    use schema::cnaef_areas::dsl::*;
+
+    cnaef_areas.order(code).load::<CnaefArea>(conn)
+}
+
 #[derive(Error, Debug)]
 pub enum CreateExamError {
     Error(DieselError),
@@ -142,50 +166,136 @@ pub fn create_exam(
     }
 }
 
+/// The codes of every exam any course asks for
+pub fn get_exam_codes(conn: &mut SqliteConnection) -> Result<Vec<String>, DieselError> {
+    use schema::exams::dsl::*;
+
+    exams.select(code).order(code).load::<String>(conn)
+}
+
+/// Code and name of every exam any course asks for
+pub fn get_exam_names(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<(String, Option<String>)>, DieselError> {
+    use schema::exams::dsl::*;
+
+    exams.select((code, name)).order(code).load(conn)
+}
+
 pub fn create_mandatory_exam(
     conn: &mut SqliteConnection,
-    exam: i32,
-    main: i32,
-) -> Result<MandatoryExam, ()> {
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    exam: &str,
+) {
     use schema::mandatory_exams;
 
-    let new_exam = NewMandatoryExam { exam, main };
+    let new_mandatory_exam = NewMandatoryExam {
+        guide_year,
+        institution,
+        course,
+        exam,
+    };
 
-    let result = diesel::insert_into(mandatory_exams::table)
-        .values(&new_exam)
-        .get_result::<MandatoryExam>(conn);
+    let insert_result = diesel::insert_into(mandatory_exams::table)
+        .values(&new_mandatory_exam)
+        .on_conflict_do_nothing()
+        .execute(conn);
 
-    match result {
-        Ok(result) => Ok(result),
-        Err(_) => Err(()),
+    if let Err(err) = insert_result {
+        info!("{}", err);
     }
 }
 
-pub fn create_main(conn: &mut SqliteConnection, ects: i32, institution: i32) -> Result<Main, ()> {
-    use schema::main;
+/// So exams a course no longer asks for in a guide don't stay behind when it's crawled again
+pub fn delete_mandatory_exams(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+    institution_val: &str,
+    course_val: &str,
+) {
+    use schema::mandatory_exams::dsl::*;
 
-    let new_main = NewMain { ects, institution };
-    let result = diesel::insert_into(main::table)
-        .values(&new_main)
-        .get_result::<Main>(conn);
-    match result {
-        Ok(result) => Ok(result),
-        Err(_) => Err(()),
+    let delete_result = diesel::delete(
+        mandatory_exams
+            .filter(guide_year.eq(guide_year_val))
+            .filter(institution.eq(institution_val))
+            .filter(course.eq(course_val)),
+    )
+    .execute(conn);
+
+    if let Err(err) = delete_result {
+        info!("{}", err);
     }
 }
 
-/*fn my_func<T: AsRef<str>>(list: &[T]) { ... }*/
-//TODO: take a look
+/// Inserts a course of a guide, or updates its name
+pub fn create_course(conn: &mut SqliteConnection, guide_year: i32, code: &str, name: Option<&str>) {
+    use schema::courses;
+
+    let new_course = NewCourse {
+        guide_year,
+        code,
+        name,
+    };
+
+    let insert_result = diesel::insert_into(courses::table)
+        .values(&new_course)
+        .on_conflict((courses::guide_year, courses::code))
+        .do_update()
+        .set(&new_course)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Inserts what a guide says about a course at an institution, or updates it
+pub fn create_course_institution(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    ects: Option<i32>,
+) {
+    use schema::course_institution;
+
+    let new_course_institution = NewCourseInstitution {
+        guide_year,
+        institution,
+        course,
+        ects,
+    };
+
+    let insert_result = diesel::insert_into(course_institution::table)
+        .values(&new_course_institution)
+        .on_conflict((
+            course_institution::guide_year,
+            course_institution::institution,
+            course_institution::course,
+        ))
+        .do_update()
+        .set(&new_course_institution)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Inserts an institution as a guide has it, or updates it
 pub fn create_institution(
     conn: &mut SqliteConnection,
+    guide_year_val: i32,
     code_val: &str,
     name_val: &str,
     address_val: impl Iterator<Item = impl AsRef<str>>,
     phone_numbers_val: impl Iterator<Item = impl AsRef<str>>,
     email_addresses_val: impl Iterator<Item = impl AsRef<str>>,
-) -> Result<Institution, ()> {
+) {
     use schema::institutions;
-    use schema::institutions::dsl::*;
 
     let mut addr: String = "".to_string();
 
@@ -206,6 +316,7 @@ pub fn create_institution(
     }
 
     let new_institution = NewInstitution {
+        guide_year: guide_year_val,
         code: code_val,
         name: name_val,
         address: &addr,
@@ -213,37 +324,680 @@ pub fn create_institution(
         email_addresses: &email_addresses_val_1,
     };
 
-    let result = conn.transaction(|conn| {
-        let mut result = diesel::insert_into(institutions::table)
-            .values(&new_institution)
-            .get_result::<Institution>(conn);
-        if let Err(ref err) = result {
-            match err {
-                DieselError::DatabaseError(err, _) => match err {
-                    diesel::result::DatabaseErrorKind::UniqueViolation => {
-                        result = institutions
-                            .filter(code.eq(code_val))
-                            .first::<Institution>(conn);
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
-        }
-        result
-    });
+    let insert_result = diesel::insert_into(institutions::table)
+        .values(&new_institution)
+        .on_conflict((institutions::guide_year, institutions::code))
+        .do_update()
+        .set(&new_institution)
+        .execute(conn);
 
-    match result {
-        Ok(result) => Ok(result),
-        Err(_err) => Err(()),
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Inserts a failed page, or updates it if it already failed before
+pub fn create_failed_page(
+    conn: &mut SqliteConnection,
+    url: &str,
+    state: Option<&str>,
+    error_kind: &str,
+    attempts: i32,
+    last_error: &str,
+) {
+    use schema::failed_pages;
+
+    let new_failed_page = NewFailedPage {
+        url,
+        state,
+        error_kind,
+        attempts,
+        last_error,
+    };
+
+    let insert_result = diesel::insert_into(failed_pages::table)
+        .values(&new_failed_page)
+        .on_conflict(failed_pages::url)
+        .do_update()
+        .set(&new_failed_page)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn delete_failed_page(conn: &mut SqliteConnection, url_val: &str) {
+    use schema::failed_pages::dsl::*;
+
+    let delete_result = diesel::delete(failed_pages.filter(url.eq(url_val))).execute(conn);
+
+    if let Err(err) = delete_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_failed_pages(conn: &mut SqliteConnection) -> Result<Vec<FailedPage>, DieselError> {
+    use schema::failed_pages::dsl::*;
+
+    failed_pages.load::<FailedPage>(conn)
+}
+
+pub fn create_run(conn: &mut SqliteConnection, started_at: &str) -> Result<Run, DieselError> {
+    use schema::runs;
+
+    diesel::insert_into(runs::table)
+        .values(&NewRun { started_at })
+        .get_result::<Run>(conn)
+}
+
+/// Records what a run covered, once it's done
+pub fn update_run(
+    conn: &mut SqliteConnection,
+    run_val: i32,
+    guide_year_val: Option<i32>,
+    partial_val: bool,
+) {
+    use schema::runs::dsl::*;
+
+    let update_result = diesel::update(runs.find(run_val))
+        .set((guide_year.eq(guide_year_val), partial.eq(partial_val)))
+        .execute(conn);
+
+    if let Err(err) = update_result {
+        info!("{}", err);
+    }
+}
+
+/// Guide years some run saw every course of
+pub fn get_complete_guide_years(conn: &mut SqliteConnection) -> Result<Vec<i32>, DieselError> {
+    use schema::runs::dsl::*;
+
+    Ok(runs
+        .filter(partial.eq(false))
+        .select(guide_year)
+        .distinct()
+        .load::<Option<i32>>(conn)?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+/// Ignores content that was already recorded for the same course in the same run
+#[allow(clippy::too_many_arguments)]
+pub fn create_unrecognized_content(
+    conn: &mut SqliteConnection,
+    run: i32,
+    institution: &str,
+    course: &str,
+    kind: &str,
+    name: &str,
+    raw_value: Option<&str>,
+    snippet: &str,
+) {
+    use schema::unrecognized_content;
+
+    let new_unrecognized_content = NewUnrecognizedContent {
+        run,
+        institution,
+        course,
+        kind,
+        name,
+        raw_value,
+        snippet,
+    };
+
+    let insert_result = diesel::insert_into(unrecognized_content::table)
+        .values(&new_unrecognized_content)
+        .on_conflict_do_nothing()
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Ignores diagnostics that were already recorded for the same course in the same run
+#[allow(clippy::too_many_arguments)]
+pub fn create_parse_diagnostic(
+    conn: &mut SqliteConnection,
+    run: i32,
+    institution: &str,
+    course: &str,
+    section: &str,
+    field: &str,
+    raw_value: &str,
+    error: &str,
+) {
+    use schema::parse_diagnostics;
+
+    let new_parse_diagnostic = NewParseDiagnostic {
+        run,
+        institution,
+        course,
+        section,
+        field,
+        raw_value,
+        error,
+    };
+
+    let insert_result = diesel::insert_into(parse_diagnostics::table)
+        .values(&new_parse_diagnostic)
+        .on_conflict_do_nothing()
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
     }
 }
 
+/// Everything recorded, or only what a given run recorded
+pub fn get_unrecognized_content(
+    conn: &mut SqliteConnection,
+    run_val: Option<i32>,
+) -> Result<Vec<UnrecognizedContent>, DieselError> {
+    use schema::unrecognized_content::dsl::*;
+
+    match run_val {
+        Some(run_val) => unrecognized_content
+            .filter(run.eq(run_val))
+            .load::<UnrecognizedContent>(conn),
+        None => unrecognized_content.load::<UnrecognizedContent>(conn),
+    }
+}
+
+pub fn create_exam_requirement(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    choice: i32,
+    option: i32,
+    exam: &str,
+) {
+    use schema::exam_requirements;
+
+    let new_exam_requirement = NewExamRequirement {
+        guide_year,
+        institution,
+        course,
+        choice,
+        option,
+        exam,
+    };
+
+    let insert_result = diesel::insert_into(exam_requirements::table)
+        .values(&new_exam_requirement)
+        .on_conflict_do_nothing()
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// So a course crawled again doesn't keep the exams it stopped requiring
+pub fn delete_exam_requirements(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+    institution_val: &str,
+    course_val: &str,
+) {
+    use schema::exam_requirements::dsl::*;
+
+    let delete_result = diesel::delete(
+        exam_requirements
+            .filter(guide_year.eq(guide_year_val))
+            .filter(institution.eq(institution_val))
+            .filter(course.eq(course_val)),
+    )
+    .execute(conn);
+
+    if let Err(err) = delete_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_exam_requirements(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<ExamRequirement>, DieselError> {
+    use schema::exam_requirements::dsl::*;
+
+    exam_requirements
+        .filter(guide_year.eq(guide_year_val))
+        .order((institution, course, choice, option))
+        .load::<ExamRequirement>(conn)
+}
+
+/// Inserts the minimum grades of a course, or replaces them
+pub fn create_minimum_grades(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    application: Option<f64>,
+    exams: Option<f64>,
+) {
+    use schema::minimum_grades;
+
+    let new_minimum_grades = NewMinimumGrades {
+        guide_year,
+        institution,
+        course,
+        application,
+        exams,
+    };
+
+    let insert_result = diesel::insert_into(minimum_grades::table)
+        .values(&new_minimum_grades)
+        .on_conflict((
+            minimum_grades::guide_year,
+            minimum_grades::institution,
+            minimum_grades::course,
+        ))
+        .do_update()
+        .set(&new_minimum_grades)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_minimum_grades(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<MinimumGrades>, DieselError> {
+    use schema::minimum_grades::dsl::*;
+
+    minimum_grades
+        .filter(guide_year.eq(guide_year_val))
+        .load::<MinimumGrades>(conn)
+}
+
+/// Inserts the formula of a course, or replaces it
+pub fn create_formula(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    secondary_average: Option<i32>,
+    exams: Option<i32>,
+) {
+    use schema::formulas;
+
+    let new_formula = NewFormula {
+        guide_year,
+        institution,
+        course,
+        secondary_average,
+        exams,
+    };
+
+    let insert_result = diesel::insert_into(formulas::table)
+        .values(&new_formula)
+        .on_conflict((
+            formulas::guide_year,
+            formulas::institution,
+            formulas::course,
+        ))
+        .do_update()
+        .set(&new_formula)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_formulas(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<Formula>, DieselError> {
+    use schema::formulas::dsl::*;
+
+    formulas
+        .filter(guide_year.eq(guide_year_val))
+        .load::<Formula>(conn)
+}
+
+/// Inserts the statistics of a phase, or updates them. Values a page no longer shows are kept, so
+/// the rows add up to a time series across runs
+#[allow(clippy::too_many_arguments)]
+pub fn create_statistics(
+    conn: &mut SqliteConnection,
+    institution: &str,
+    course: &str,
+    year: i32,
+    phase: i32,
+    vacancies: Option<i32>,
+    applicants: Option<i32>,
+    placed: Option<i32>,
+    last_placed_grade: Option<f64>,
+) {
+    use schema::statistics;
+
+    let new_statistics = NewStatistics {
+        institution,
+        course,
+        year,
+        phase,
+        vacancies,
+        applicants,
+        placed,
+        last_placed_grade,
+    };
+
+    let insert_result = diesel::insert_into(statistics::table)
+        .values(&new_statistics)
+        .on_conflict((
+            statistics::institution,
+            statistics::course,
+            statistics::year,
+            statistics::phase,
+        ))
+        .do_update()
+        .set(&new_statistics)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Newest year first, then by phase
+pub fn get_statistics(conn: &mut SqliteConnection) -> Result<Vec<Statistics>, DieselError> {
+    use schema::statistics::dsl::*;
+
+    statistics
+        .order((institution, course, year.desc(), phase))
+        .load::<Statistics>(conn)
+}
+
+/// One phase of every course, oldest year first
+pub fn get_statistics_series(
+    conn: &mut SqliteConnection,
+    phase_val: i32,
+) -> Result<Vec<Statistics>, DieselError> {
+    use schema::statistics::dsl::*;
+
+    statistics
+        .filter(phase.eq(phase_val))
+        .order((institution, course, year))
+        .load::<Statistics>(conn)
+}
+
+/// Inserts the CNAEF area of a course, with the broad group and narrow field it's under, or
+/// replaces it
+pub fn create_course_area(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    broad_group: &str,
+    narrow_field: Option<&str>,
+    cnaef_area: &str,
+) {
+    use schema::course_areas;
+
+    let new_course_area = NewCourseArea {
+        guide_year,
+        institution,
+        course,
+        broad_group,
+        narrow_field,
+        cnaef_area,
+    };
+
+    let insert_result = diesel::insert_into(course_areas::table)
+        .values(&new_course_area)
+        .on_conflict((
+            course_areas::guide_year,
+            course_areas::institution,
+            course_areas::course,
+        ))
+        .do_update()
+        .set(&new_course_area)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_course_areas(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<CourseArea>, DieselError> {
+    use schema::course_areas::dsl::*;
+
+    course_areas
+        .filter(guide_year.eq(guide_year_val))
+        .load::<CourseArea>(conn)
+}
+
+/// The courses under a CNAEF area of any level, `4`, `48` or `481`
+pub fn get_course_areas_under(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+    area_val: &str,
+) -> Result<Vec<CourseArea>, DieselError> {
+    use schema::course_areas::dsl::*;
+
+    let query = course_areas
+        .filter(guide_year.eq(guide_year_val))
+        .order((institution, course))
+        .into_boxed();
+    let query = match area_val.len() {
+        1 => query.filter(broad_group.eq(area_val)),
+        2 => query.filter(narrow_field.eq(area_val)),
+        _ => query.filter(cnaef_area.eq(area_val)),
+    };
+    query.load::<CourseArea>(conn)
+}
+
+/// Records that a guide has a course, or updates its name
+pub fn create_guide_course(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    name: Option<&str>,
+) {
+    use schema::guide_courses;
+
+    let new_guide_course = NewGuideCourse {
+        guide_year,
+        institution,
+        course,
+        name,
+    };
+
+    let insert_result = diesel::insert_into(guide_courses::table)
+        .values(&new_guide_course)
+        .on_conflict((
+            guide_courses::guide_year,
+            guide_courses::institution,
+            guide_courses::course,
+        ))
+        .do_update()
+        .set(&new_guide_course)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_guide_courses(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<GuideCourse>, DieselError> {
+    use schema::guide_courses::dsl::*;
+
+    guide_courses
+        .filter(guide_year.eq(guide_year_val))
+        .order((institution, course))
+        .load::<GuideCourse>(conn)
+}
+
+/// Every guide year crawled so far, newest first
+pub fn get_guide_years(conn: &mut SqliteConnection) -> Result<Vec<i32>, DieselError> {
+    use schema::guide_courses::dsl::*;
+
+    guide_courses
+        .select(guide_year)
+        .distinct()
+        .order(guide_year.desc())
+        .load::<i32>(conn)
+}
+
+/// Code and address of every institution in a guide
+pub fn get_institution_addresses(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<(String, Option<String>)>, DieselError> {
+    use schema::institutions::dsl::*;
+
+    institutions
+        .filter(guide_year.eq(guide_year_val))
+        .select((code, address))
+        .load(conn)
+}
+
+/// Ignores anomalies that were already recorded for the same course in the same run
+pub fn create_anomaly(
+    conn: &mut SqliteConnection,
+    run: i32,
+    institution: &str,
+    course: &str,
+    kind: &str,
+    detail: &str,
+) {
+    use schema::anomalies;
+
+    let new_anomaly = NewAnomaly {
+        run,
+        institution,
+        course,
+        kind,
+        detail,
+    };
+
+    let insert_result = diesel::insert_into(anomalies::table)
+        .values(&new_anomaly)
+        .on_conflict_do_nothing()
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+/// Everything recorded, or only what a given run recorded
+pub fn get_anomalies(
+    conn: &mut SqliteConnection,
+    run_val: Option<i32>,
+) -> Result<Vec<Anomaly>, DieselError> {
+    use schema::anomalies::dsl::*;
+
+    match run_val {
+        Some(run_val) => anomalies
+            .filter(run.eq(run_val))
+            .order((institution, course, kind))
+            .load::<Anomaly>(conn),
+        None => anomalies
+            .order((run, institution, course, kind))
+            .load::<Anomaly>(conn),
+    }
+}
+
+/// Inserts the workload of a course, or replaces it
+pub fn create_workload(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course: &str,
+    months: Option<i32>,
+    ects: Option<i32>,
+    ects_per_year: Option<f64>,
+) {
+    use schema::workloads;
+
+    let new_workload = NewWorkload {
+        guide_year,
+        institution,
+        course,
+        months,
+        ects,
+        ects_per_year,
+    };
+
+    let insert_result = diesel::insert_into(workloads::table)
+        .values(&new_workload)
+        .on_conflict((
+            workloads::guide_year,
+            workloads::institution,
+            workloads::course,
+        ))
+        .do_update()
+        .set(&new_workload)
+        .execute(conn);
+
+    if let Err(err) = insert_result {
+        info!("{}", err);
+    }
+}
+
+pub fn get_workloads(
+    conn: &mut SqliteConnection,
+    guide_year_val: i32,
+) -> Result<Vec<Workload>, DieselError> {
+    use schema::workloads::dsl::*;
+
+    workloads
+        .filter(guide_year.eq(guide_year_val))
+        .order((institution, course))
+        .load::<Workload>(conn)
+}
+
+/// Connects to the database, keeping whatever it already holds
 pub fn establish_connection() -> SqliteConnection {
     dotenv().ok();
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    fs::remove_file(&database_url).ok();
     SqliteConnection::establish(&database_url)
         .expect(&format!("Error connecting to {}", database_url))
 }
+
+/// Deletes what earlier crawls saved about courses, so this one starts over. Runs and what was
+/// saved per run, failed pages and reference data like the CNAEF taxonomy stay
+pub fn establish_fresh_connection() -> SqliteConnection {
+    let mut conn = establish_connection();
+    conn.run_pending_migrations(MIGRATIONS)
+        .expect("Please migration god, be good!");
+    delete_course_data(&mut conn).expect("Couldn't delete what earlier crawls saved");
+    conn
+}
+
+fn delete_course_data(conn: &mut SqliteConnection) -> Result<(), DieselError> {
+    use schema::*;
+
+    conn.transaction(|conn| {
+        // whatever points at another table goes first
+        diesel::delete(mandatory_exams::table).execute(conn)?;
+        diesel::delete(exam_requirements::table).execute(conn)?;
+        diesel::delete(minimum_grades::table).execute(conn)?;
+        diesel::delete(formulas::table).execute(conn)?;
+        diesel::delete(course_areas::table).execute(conn)?;
+        diesel::delete(guide_courses::table).execute(conn)?;
+        diesel::delete(workloads::table).execute(conn)?;
+        diesel::delete(statistics::table).execute(conn)?;
+        diesel::delete(durations::table).execute(conn)?;
+        diesel::delete(course_institution::table).execute(conn)?;
+        diesel::delete(courses::table).execute(conn)?;
+        diesel::delete(institutions::table).execute(conn)?;
+        Ok(())
+    })
+}
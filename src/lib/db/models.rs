@@ -1,21 +1,28 @@
 use super::schema::{
-    cnaef_areas, duration_units, durations, exams, institutions, main, mandatory_exams,
+    anomalies, cnaef_areas, course_areas, course_institution, courses, duration_units, durations,
+    exam_requirements, exams, failed_pages, formulas, guide_courses, institutions, mandatory_exams,
+    minimum_grades, parse_diagnostics, runs, statistics, unrecognized_content, workloads,
 };
 use diesel::AsChangeset;
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[diesel(table_name = durations)]
-pub struct NewDuration {
-    pub rowid: i32,
-    pub unit: i32,
-    pub ammount: i32,
+#[diesel(treat_none_as_null = true)]
+pub struct NewDuration<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub unit: Option<&'a str>,
+    pub ammount: Option<i32>,
 }
 
 #[derive(Queryable)]
 pub struct Duration {
-    rowid: i32,
-    unit: i32,
-    ammount: i32,
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub unit: Option<String>,
+    pub ammount: Option<i32>,
 }
 
 //--------------------
@@ -28,8 +35,7 @@ pub struct NewDurationUnit<'a> {
 
 #[derive(Queryable)]
 pub struct DurationUnit {
-    pub rowid: i32,
-    pub unit: String,
+    pub name: String,
 }
 
 //---------------
@@ -39,13 +45,16 @@ pub struct DurationUnit {
 pub struct NewCnaefArea<'a> {
     pub code: &'a str,
     pub name: &'a str,
+    pub level: i32,
+    pub parent: Option<&'a str>,
 }
 
 #[derive(Queryable)]
 pub struct CnaefArea {
-    pub rowid: i32,
     pub code: String,
-    pub name: String,
+    pub name: Option<String>,
+    pub level: i32,
+    pub parent: Option<String>,
 }
 
 //---------------
@@ -64,43 +73,69 @@ pub struct Exam {
     pub name: String,
 }
 
-// main
+// courses
 
-#[derive(Insertable)]
-#[diesel(table_name = main)]
-pub struct NewMain {
-    pub ects: i32,
-    pub institution: i32,
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = courses)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewCourse<'a> {
+    pub guide_year: i32,
+    pub code: &'a str,
+    pub name: Option<&'a str>,
 }
 
 #[derive(Queryable)]
-pub struct Main {
-    pub rowid: i32,
-    pub ects: i32,
-    pub institution: i32,
+pub struct Course {
+    pub guide_year: i32,
+    pub code: String,
+    pub name: Option<String>,
+}
+
+// course institution
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = course_institution)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewCourseInstitution<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub ects: Option<i32>,
+}
+
+#[derive(Queryable)]
+pub struct CourseInstitution {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub ects: Option<i32>,
 }
 
 // mandatory exams
 
 #[derive(Insertable)]
 #[diesel(table_name = mandatory_exams)]
-pub struct NewMandatoryExam {
-    pub exam: i32,
-    pub main: i32,
+pub struct NewMandatoryExam<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub exam: &'a str,
 }
 
 #[derive(Queryable)]
 pub struct MandatoryExam {
-    pub rowid: i32,
-    pub exam: i32,
-    pub main: i32,
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub exam: String,
 }
 
 // institutions
 
-#[derive(Insertable)]
+#[derive(Insertable, AsChangeset)]
 #[diesel(table_name = institutions)]
 pub struct NewInstitution<'a> {
+    pub guide_year: i32,
     pub code: &'a str,
     pub name: &'a str,
     pub address: &'a str,
@@ -110,10 +145,268 @@ pub struct NewInstitution<'a> {
 
 #[derive(Queryable)]
 pub struct Institution {
-    pub rowid: i32,
+    pub guide_year: i32,
     pub code: String,
+    pub name: Option<String>,
+    pub address: Option<String>,
+    pub phone_numbers: Option<String>,
+    pub email_addresses: Option<String>,
+}
+
+// failed pages
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = failed_pages)]
+pub struct NewFailedPage<'a> {
+    pub url: &'a str,
+    pub state: Option<&'a str>,
+    pub error_kind: &'a str,
+    pub attempts: i32,
+    pub last_error: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct FailedPage {
+    pub url: String,
+    pub state: Option<String>,
+    pub error_kind: String,
+    pub attempts: i32,
+    pub last_error: String,
+}
+
+// runs
+
+#[derive(Insertable)]
+#[diesel(table_name = runs)]
+pub struct NewRun<'a> {
+    pub started_at: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct Run {
+    pub id: i32,
+    pub started_at: String,
+    pub guide_year: Option<i32>,
+    /// Didn't see every course of its guide, or never finished
+    pub partial: bool,
+}
+
+// unrecognized content
+
+#[derive(Insertable)]
+#[diesel(table_name = unrecognized_content)]
+pub struct NewUnrecognizedContent<'a> {
+    pub run: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub kind: &'a str,
+    pub name: &'a str,
+    pub raw_value: Option<&'a str>,
+    pub snippet: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct UnrecognizedContent {
+    pub run: i32,
+    pub institution: String,
+    pub course: String,
+    pub kind: String,
     pub name: String,
-    pub address: String,
-    pub phone_numbers: String,
-    pub email_addresses: String,
+    pub raw_value: Option<String>,
+    pub snippet: String,
+}
+
+// parse diagnostics
+
+#[derive(Insertable)]
+#[diesel(table_name = parse_diagnostics)]
+pub struct NewParseDiagnostic<'a> {
+    pub run: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub section: &'a str,
+    pub field: &'a str,
+    pub raw_value: &'a str,
+    pub error: &'a str,
+}
+
+// exam requirements
+
+#[derive(Insertable)]
+#[diesel(table_name = exam_requirements)]
+pub struct NewExamRequirement<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub choice: i32,
+    pub option: i32,
+    pub exam: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct ExamRequirement {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub choice: i32,
+    pub option: i32,
+    pub exam: String,
+}
+
+// minimum grades
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = minimum_grades)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewMinimumGrades<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub application: Option<f64>,
+    pub exams: Option<f64>,
+}
+
+#[derive(Queryable)]
+pub struct MinimumGrades {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub application: Option<f64>,
+    pub exams: Option<f64>,
+}
+
+// formulas
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = formulas)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewFormula<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub secondary_average: Option<i32>,
+    pub exams: Option<i32>,
+}
+
+#[derive(Queryable)]
+pub struct Formula {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub secondary_average: Option<i32>,
+    pub exams: Option<i32>,
+}
+
+// statistics
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = statistics)]
+pub struct NewStatistics<'a> {
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub year: i32,
+    pub phase: i32,
+    pub vacancies: Option<i32>,
+    pub placed: Option<i32>,
+    pub last_placed_grade: Option<f64>,
+    pub applicants: Option<i32>,
+}
+
+#[derive(Queryable)]
+pub struct Statistics {
+    pub institution: String,
+    pub course: String,
+    pub year: i32,
+    pub phase: i32,
+    pub vacancies: Option<i32>,
+    pub placed: Option<i32>,
+    pub last_placed_grade: Option<f64>,
+    pub applicants: Option<i32>,
+}
+
+// course areas
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = course_areas)]
+pub struct NewCourseArea<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub broad_group: &'a str,
+    pub narrow_field: Option<&'a str>,
+    pub cnaef_area: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct CourseArea {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub broad_group: String,
+    pub narrow_field: Option<String>,
+    pub cnaef_area: String,
+}
+
+// guide courses
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = guide_courses)]
+pub struct NewGuideCourse<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub name: Option<&'a str>,
+}
+
+#[derive(Queryable)]
+pub struct GuideCourse {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub name: Option<String>,
+}
+
+// anomalies
+
+#[derive(Insertable)]
+#[diesel(table_name = anomalies)]
+pub struct NewAnomaly<'a> {
+    pub run: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub kind: &'a str,
+    pub detail: &'a str,
+}
+
+#[derive(Queryable)]
+pub struct Anomaly {
+    pub run: i32,
+    pub institution: String,
+    pub course: String,
+    pub kind: String,
+    pub detail: String,
+}
+
+// workloads
+
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = workloads)]
+#[diesel(treat_none_as_null = true)]
+pub struct NewWorkload<'a> {
+    pub guide_year: i32,
+    pub institution: &'a str,
+    pub course: &'a str,
+    pub months: Option<i32>,
+    pub ects: Option<i32>,
+    pub ects_per_year: Option<f64>,
+}
+
+#[derive(Queryable)]
+pub struct Workload {
+    pub guide_year: i32,
+    pub institution: String,
+    pub course: String,
+    pub months: Option<i32>,
+    pub ects: Option<i32>,
+    pub ects_per_year: Option<f64>,
 }
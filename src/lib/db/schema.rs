@@ -1,7 +1,19 @@
+table! {
+    anomalies (run, institution, course, kind) {
+        run -> Integer,
+        institution -> Text,
+        course -> Text,
+        kind -> Text,
+        detail -> Text,
+    }
+}
+
 table! {
     cnaef_areas (code) {
         code -> Text,
         name -> Nullable<Text>,
+        level -> Integer,
+        parent -> Nullable<Text>,
     }
 }
 
@@ -12,15 +24,28 @@ table! {
 }
 
 table! {
-    course_institution (institution, course) {
-        ects -> Nullable<Integer>,
+    course_areas (guide_year, institution, course) {
+        guide_year -> Integer,
         institution -> Text,
         course -> Text,
+        broad_group -> Text,
+        narrow_field -> Nullable<Text>,
+        cnaef_area -> Text,
     }
 }
 
 table! {
-    courses (code) {
+    course_institution (guide_year, institution, course) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        ects -> Nullable<Integer>,
+    }
+}
+
+table! {
+    courses (guide_year, code) {
+        guide_year -> Integer,
         code -> Text,
         name -> Nullable<Text>,
     }
@@ -39,7 +64,8 @@ table! {
 }
 
 table! {
-    durations (institution, course) {
+    durations (guide_year, institution, course) {
+        guide_year -> Integer,
         institution -> Text,
         course -> Text,
         unit -> Nullable<Text>,
@@ -53,6 +79,17 @@ table! {
     }
 }
 
+table! {
+    exam_requirements (guide_year, institution, course, choice, option, exam) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        choice -> Integer,
+        option -> Integer,
+        exam -> Text,
+    }
+}
+
 table! {
     exams (code) {
         code -> Text,
@@ -61,7 +98,37 @@ table! {
 }
 
 table! {
-    institutions (code) {
+    failed_pages (url) {
+        url -> Text,
+        state -> Nullable<Text>,
+        error_kind -> Text,
+        attempts -> Integer,
+        last_error -> Text,
+    }
+}
+
+table! {
+    formulas (guide_year, institution, course) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        secondary_average -> Nullable<Integer>,
+        exams -> Nullable<Integer>,
+    }
+}
+
+table! {
+    guide_courses (guide_year, institution, course) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        name -> Nullable<Text>,
+    }
+}
+
+table! {
+    institutions (guide_year, code) {
+        guide_year -> Integer,
         code -> Text,
         name -> Nullable<Text>,
         address -> Nullable<Text>,
@@ -71,28 +138,111 @@ table! {
 }
 
 table! {
-    mandatory_exams (exam, institution, course) {
-        exam -> Text,
+    minimum_grades (guide_year, institution, course) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        application -> Nullable<Double>,
+        exams -> Nullable<Double>,
+    }
+}
+
+table! {
+    parse_diagnostics (run, institution, course, section, field, raw_value) {
+        run -> Integer,
+        institution -> Text,
+        course -> Text,
+        section -> Text,
+        field -> Text,
+        raw_value -> Text,
+        error -> Text,
+    }
+}
+
+table! {
+    runs (id) {
+        id -> Integer,
+        started_at -> Text,
+        guide_year -> Nullable<Integer>,
+        partial -> Bool,
+    }
+}
+
+table! {
+    statistics (institution, course, year, phase) {
+        institution -> Text,
+        course -> Text,
+        year -> Integer,
+        phase -> Integer,
+        vacancies -> Nullable<Integer>,
+        placed -> Nullable<Integer>,
+        last_placed_grade -> Nullable<Double>,
+        applicants -> Nullable<Integer>,
+    }
+}
+
+table! {
+    unrecognized_content (run, institution, course, kind, name) {
+        run -> Integer,
         institution -> Text,
         course -> Text,
+        kind -> Text,
+        name -> Text,
+        raw_value -> Nullable<Text>,
+        snippet -> Text,
+    }
+}
+
+table! {
+    workloads (guide_year, institution, course) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        months -> Nullable<Integer>,
+        ects -> Nullable<Integer>,
+        ects_per_year -> Nullable<Double>,
+    }
+}
+
+table! {
+    mandatory_exams (guide_year, institution, course, exam) {
+        guide_year -> Integer,
+        institution -> Text,
+        course -> Text,
+        exam -> Text,
     }
 }
 
-joinable!(course_institution -> courses (course));
-joinable!(course_institution -> institutions (institution));
+joinable!(anomalies -> runs (run));
+joinable!(course_areas -> cnaef_areas (cnaef_area));
 joinable!(durations -> duration_units (unit));
+joinable!(exam_requirements -> exams (exam));
 joinable!(mandatory_exams -> exams (exam));
+joinable!(parse_diagnostics -> runs (run));
+joinable!(unrecognized_content -> runs (run));
 
 allow_tables_to_appear_in_same_query!(
+    anomalies,
     cnaef_areas,
     contests,
+    course_areas,
     course_institution,
     courses,
     degrees,
     duration_units,
     durations,
     education_types,
+    exam_requirements,
     exams,
+    failed_pages,
+    formulas,
+    guide_courses,
     institutions,
     mandatory_exams,
+    minimum_grades,
+    parse_diagnostics,
+    runs,
+    statistics,
+    unrecognized_content,
+    workloads,
 );
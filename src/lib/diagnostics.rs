@@ -0,0 +1,37 @@
+use std::fmt::Display;
+
+/// A value on a course page that was there but couldn't be parsed
+#[derive(Debug, Clone)]
+pub(crate) struct ParseDiagnostic {
+    /// `characteristics`, `exams`, ...
+    pub(crate) section: &'static str,
+    pub(crate) field: String,
+    pub(crate) raw_value: String,
+    pub(crate) error: String,
+}
+
+impl ParseDiagnostic {
+    pub(crate) fn new(
+        section: &'static str,
+        field: &str,
+        raw_value: &str,
+        error: impl Display,
+    ) -> Self {
+        ParseDiagnostic {
+            section,
+            field: field.to_string(),
+            raw_value: raw_value.to_string(),
+            error: error.to_string(),
+        }
+    }
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}/{} \"{}\": {}",
+            self.section, self.field, self.raw_value, self.error
+        )
+    }
+}
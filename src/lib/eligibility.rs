@@ -0,0 +1,418 @@
+//! Which courses a candidate can apply to, going by the entrance exams they took.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Display;
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use super::application::{best_application_grade, formulas};
+use super::course_url::CourseUrl;
+use super::db::{get_exam_requirements, get_guide_courses, get_minimum_grades};
+use super::exams::{ExamCode, ExamRequirement};
+use super::grades::{Formula, Grade, MinimumGrades};
+use super::profile::CandidateProfile;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Eligibility {
+    /// Every combination of exams the candidate can apply with
+    Eligible(Vec<BTreeSet<ExamCode>>),
+    /// The combination the candidate is closest to, and what's keeping them out of it
+    NotEligible {
+        combination: BTreeSet<ExamCode>,
+        missing: BTreeSet<ExamCode>,
+        below_minimum: BTreeSet<ExamCode>,
+        /// The best application grade they'd get, if it's under the minimum. The exams are all
+        /// there then
+        application_grade: Option<Grade>,
+    },
+    /// The course doesn't accept any combination of exams
+    Unsatisfiable,
+    /// No exam requirements were saved for the course, e.g. its section didn't parse
+    Unknown,
+}
+
+impl Eligibility {
+    pub fn is_eligible(&self) -> bool {
+        matches!(self, Eligibility::Eligible(_))
+    }
+}
+
+/// Checks the exams of a candidate against a course's requirement and minimum grades. The
+/// minimum application grade is only checked when the formula and the candidate's secondary
+/// average give one
+pub(crate) fn check_eligibility(
+    requirement: &ExamRequirement,
+    minimum_grades: MinimumGrades,
+    formula: &Formula,
+    profile: &CandidateProfile,
+) -> Eligibility {
+    let passed = profile.passed(minimum_grades.exams);
+    let combinations = requirement.dnf();
+
+    let matched: Vec<_> = combinations
+        .iter()
+        .filter(|combination| combination.iter().all(|exam| passed.contains(exam)))
+        .cloned()
+        .collect();
+    if !matched.is_empty() {
+        let minimum = match minimum_grades.application {
+            Some(minimum) => minimum,
+            None => return Eligibility::Eligible(matched),
+        };
+        let (enough, under): (Vec<_>, Vec<_>) = matched.into_iter().partition(|combination| {
+            best_application_grade(formula, std::slice::from_ref(combination), profile)
+                .map_or(true, |(grade, _)| grade >= minimum)
+        });
+        if !enough.is_empty() {
+            return Eligibility::Eligible(enough);
+        }
+        // every combination left has a grade, under the minimum
+        let (grade, combination) = best_application_grade(formula, &under, profile).unwrap();
+        return Eligibility::NotEligible {
+            combination,
+            missing: BTreeSet::new(),
+            below_minimum: BTreeSet::new(),
+            application_grade: Some(grade),
+        };
+    }
+
+    combinations
+        .into_iter()
+        .map(|combination| {
+            let (below_minimum, missing): (BTreeSet<_>, BTreeSet<_>) = combination
+                .iter()
+                .filter(|exam| !passed.contains(*exam))
+                .cloned()
+                .partition(|exam| profile.took(exam));
+            (combination, missing, below_minimum)
+        })
+        .min_by_key(|(_, missing, below_minimum)| {
+            (missing.len() + below_minimum.len(), missing.len())
+        })
+        .map_or(
+            Eligibility::Unsatisfiable,
+            |(combination, missing, below_minimum)| Eligibility::NotEligible {
+                combination,
+                missing,
+                below_minimum,
+                application_grade: None,
+            },
+        )
+}
+
+pub struct CourseEligibility {
+    pub(crate) course: CourseUrl,
+    /// `None` if no exam requirements were saved for the course
+    pub(crate) requirement: Option<ExamRequirement>,
+    pub(crate) minimum_grades: MinimumGrades,
+    pub eligibility: Eligibility,
+}
+
+/// Every course of a guide, sorted by code, eligible or not. The ones without exam requirements
+/// are `Eligibility::Unknown`
+pub fn course_eligibility(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    profile: &CandidateProfile,
+) -> Result<Vec<CourseEligibility>> {
+    let formulas = formulas(conn, guide_year)?;
+    let mut minimum_grades: HashMap<CourseUrl, MinimumGrades> = HashMap::new();
+    for row in get_minimum_grades(conn, guide_year)? {
+        minimum_grades.insert(
+            CourseUrl::new(&row.institution, &row.course),
+            MinimumGrades {
+                application: row.application.and_then(|points| points.try_into().ok()),
+                exams: row.exams.and_then(|points| points.try_into().ok()),
+            },
+        );
+    }
+
+    let mut requirements = exam_requirements(conn, guide_year)?;
+    Ok(get_guide_courses(conn, guide_year)?
+        .into_iter()
+        .map(|row| {
+            let course = CourseUrl::new(&row.institution, &row.course);
+            let requirement = requirements.remove(&course);
+            let minimum_grades = minimum_grades.remove(&course).unwrap_or_default();
+            let formula = formulas.get(&course).copied().unwrap_or_default();
+            let eligibility = match &requirement {
+                Some(requirement) => {
+                    check_eligibility(requirement, minimum_grades, &formula, profile)
+                }
+                None => Eligibility::Unknown,
+            };
+            CourseEligibility {
+                course,
+                requirement,
+                minimum_grades,
+                eligibility,
+            }
+        })
+        .collect())
+}
+
+/// Puts the rows of `exam_requirements` of a guide back together, per course
+pub(crate) fn exam_requirements(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+) -> Result<BTreeMap<CourseUrl, ExamRequirement>> {
+    let mut courses: BTreeMap<CourseUrl, BTreeMap<i32, BTreeMap<i32, Vec<ExamCode>>>> =
+        BTreeMap::new();
+    for row in get_exam_requirements(conn, guide_year)? {
+        courses
+            .entry(CourseUrl::new(&row.institution, &row.course))
+            .or_default()
+            .entry(row.choice)
+            .or_default()
+            .entry(row.option)
+            .or_default()
+            .push(row.exam.as_str().into());
+    }
+
+    Ok(courses
+        .into_iter()
+        .map(|(course, choices)| {
+            let choices = choices.into_values().map(|options| {
+                ExamRequirement::any(options.into_values().map(|exams| {
+                    ExamRequirement::all(exams.into_iter().map(ExamRequirement::Exam))
+                }))
+            });
+            (course, ExamRequirement::all(choices))
+        })
+        .collect())
+}
+
+fn exam_list(exams: &BTreeSet<ExamCode>) -> String {
+    exams
+        .iter()
+        .map(ExamCode::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `0807/9119: eligible with 07 and 19 (needs 19 and (04 or 07))`
+impl Display for CourseEligibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.eligibility {
+            Eligibility::Eligible(combinations) => write!(
+                f,
+                "{}: eligible with {}",
+                self.course,
+                ExamRequirement::from_combinations(combinations.clone())
+            )?,
+            Eligibility::NotEligible {
+                combination,
+                missing,
+                below_minimum,
+                application_grade,
+            } => {
+                write!(
+                    f,
+                    "{}: not eligible, closest is {}",
+                    self.course,
+                    ExamRequirement::from_combinations(vec![combination.clone()])
+                )?;
+                if !missing.is_empty() {
+                    write!(f, ", missing {}", exam_list(missing))?;
+                }
+                if !below_minimum.is_empty() {
+                    write!(f, ", under the minimum exam grade")?;
+                    if let Some(minimum) = self.minimum_grades.exams {
+                        write!(f, " ({})", minimum)?;
+                    }
+                    write!(f, " in {}", exam_list(below_minimum))?;
+                }
+                if let Some(grade) = application_grade {
+                    write!(f, ", application grade {} under the minimum", grade)?;
+                    if let Some(minimum) = self.minimum_grades.application {
+                        write!(f, " ({})", minimum)?;
+                    }
+                }
+            }
+            Eligibility::Unsatisfiable => {
+                write!(f, "{}: no combination of exams is accepted", self.course)?
+            }
+            Eligibility::Unknown => write!(f, "{}: requirements unknown", self.course)?,
+        }
+        match &self.requirement {
+            Some(requirement) => write!(f, " (needs {})", requirement),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exam(code: &str) -> ExamRequirement {
+        ExamRequirement::Exam(code.into())
+    }
+
+    fn codes(codes: &[&str]) -> BTreeSet<ExamCode> {
+        codes.iter().map(|&code| code.into()).collect()
+    }
+
+    fn grade(points: f64) -> Grade {
+        Grade::try_from(points).unwrap()
+    }
+
+    fn profile(secondary_average: Option<f64>, exams: &[(&str, f64)]) -> CandidateProfile {
+        CandidateProfile {
+            name: None,
+            exams: exams
+                .iter()
+                .map(|&(code, points)| (code.to_string(), grade(points)))
+                .collect(),
+            secondary_average,
+            contingent: None,
+            preferences: Vec::new(),
+        }
+    }
+
+    /// 19 and (04 or 07)
+    fn requirement() -> ExamRequirement {
+        ExamRequirement::all([exam("19"), ExamRequirement::any([exam("04"), exam("07")])])
+    }
+
+    fn minimum_grades(application: Option<f64>, exams: Option<f64>) -> MinimumGrades {
+        MinimumGrades {
+            application: application.map(grade),
+            exams: exams.map(grade),
+        }
+    }
+
+    const HALF_AND_HALF: Formula = Formula {
+        secondary_average: Some(50),
+        exams: Some(50),
+    };
+
+    #[test]
+    fn eligible_with_every_combination_passed() {
+        let profile = profile(None, &[("04", 120.0), ("07", 110.0), ("19", 130.0)]);
+        assert_eq!(
+            check_eligibility(
+                &requirement(),
+                minimum_grades(None, Some(95.0)),
+                &HALF_AND_HALF,
+                &profile
+            ),
+            Eligibility::Eligible(vec![codes(&["04", "19"]), codes(&["07", "19"])])
+        );
+    }
+
+    #[test]
+    fn not_eligible_without_an_exam_or_under_its_minimum() {
+        let profile = profile(None, &[("07", 90.0)]);
+        assert_eq!(
+            check_eligibility(
+                &requirement(),
+                minimum_grades(None, Some(95.0)),
+                &HALF_AND_HALF,
+                &profile
+            ),
+            Eligibility::NotEligible {
+                combination: codes(&["07", "19"]),
+                missing: codes(&["19"]),
+                below_minimum: codes(&["07"]),
+                application_grade: None,
+            }
+        );
+    }
+
+    #[test]
+    fn unsatisfiable_without_any_combination() {
+        let profile = profile(None, &[("19", 150.0)]);
+        assert_eq!(
+            check_eligibility(
+                &ExamRequirement::Any(vec![]),
+                MinimumGrades::default(),
+                &HALF_AND_HALF,
+                &profile
+            ),
+            Eligibility::Unsatisfiable
+        );
+    }
+
+    #[test]
+    fn only_combinations_reaching_the_minimum_application_grade() {
+        // 140 * 50% + (150 + 180) / 2 * 50% = 152.5 with 04, 132.5 with 07
+        let profile = profile(Some(14.0), &[("04", 180.0), ("07", 100.0), ("19", 150.0)]);
+        assert_eq!(
+            check_eligibility(
+                &requirement(),
+                minimum_grades(Some(140.0), Some(95.0)),
+                &HALF_AND_HALF,
+                &profile
+            ),
+            Eligibility::Eligible(vec![codes(&["04", "19"])])
+        );
+        assert_eq!(
+            check_eligibility(
+                &requirement(),
+                minimum_grades(Some(160.0), Some(95.0)),
+                &HALF_AND_HALF,
+                &profile
+            ),
+            Eligibility::NotEligible {
+                combination: codes(&["04", "19"]),
+                missing: BTreeSet::new(),
+                below_minimum: BTreeSet::new(),
+                application_grade: Some(grade(152.5)),
+            }
+        );
+    }
+
+    #[test]
+    fn minimum_application_grade_needs_a_grade_to_check() {
+        let without_average = profile(None, &[("07", 100.0), ("19", 100.0)]);
+        let with_average = profile(Some(10.0), &[("07", 100.0), ("19", 100.0)]);
+        let minimum_grades = minimum_grades(Some(190.0), None);
+        assert!(check_eligibility(
+            &requirement(),
+            minimum_grades,
+            &HALF_AND_HALF,
+            &without_average
+        )
+        .is_eligible());
+        assert!(check_eligibility(
+            &requirement(),
+            minimum_grades,
+            &Formula::default(),
+            &with_average
+        )
+        .is_eligible());
+        assert!(!check_eligibility(
+            &requirement(),
+            minimum_grades,
+            &HALF_AND_HALF,
+            &with_average
+        )
+        .is_eligible());
+    }
+
+    #[test]
+    fn unknown_requirements_are_listed() {
+        let unknown = CourseEligibility {
+            course: CourseUrl::new("0807", "9119"),
+            requirement: None,
+            minimum_grades: MinimumGrades::default(),
+            eligibility: Eligibility::Unknown,
+        };
+        assert_eq!(unknown.to_string(), "0807/9119: requirements unknown");
+
+        let known = CourseEligibility {
+            requirement: Some(requirement()),
+            eligibility: Eligibility::Unsatisfiable,
+            ..unknown
+        };
+        assert_eq!(
+            known.to_string(),
+            format!(
+                "0807/9119: no combination of exams is accepted (needs {})",
+                requirement()
+            )
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! "Provas de Ingresso" sections, and how they should parse.
+//!
+//! Every fragment is synthetic: written after the markup of DGES's course pages, or cut from the
+//! mock server's pages in `fixtures/dges/courses` (`one-of`, `one-set-of` and
+//! `mandatory-then-one-of`). None is cut from a page DGES served yet. To add real ones, record a
+//! crawl with `--record`, take the "Provas de Ingresso" section out of the `.body` files
+//! (Windows-1252, with `&nbsp;` and `<b>` as DGES has them) and save it as UTF-8.
+//!
+//! Each case is a `<name>.html` fragment (UTF-8, starting at the `<h2>`) and a `<name>.expected`
+//! file with the parsed exams as `Exams` displays them followed by `dnf: <every combination>`, or
+//! `error: <message>`.
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use voyager::scraper::{Html, Selector};
+
+use super::{parse_exams, section_lines, ExamRequirement};
+
+pub struct CorpusCase {
+    pub name: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl CorpusCase {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+/// Parses every fragment in `directory`, sorted by name
+pub fn check_corpus(directory: &Path) -> Result<Vec<CorpusCase>> {
+    let mut fragments: Vec<_> = fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "html")
+        })
+        .collect();
+    fragments.sort();
+
+    let mut cases = Vec::new();
+    for fragment in fragments {
+        let name = fragment
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let expected = fs::read_to_string(fragment.with_extension("expected"))?
+            .trim()
+            .to_string();
+        let actual = match parse_fragment(&fs::read_to_string(&fragment)?) {
+            Ok(exams) => exams,
+            Err(err) => format!("error: {}", err),
+        };
+        cases.push(CorpusCase {
+            name,
+            expected,
+            actual,
+        });
+    }
+    Ok(cases)
+}
+
+fn parse_fragment(html: &str) -> Result<String> {
+    let html = Html::parse_fragment(html);
+    let header_selector = Selector::parse("h2").unwrap();
+    let header = match html
+        .select(&header_selector)
+        .find(|header| header.inner_html() == "Provas de Ingresso")
+    {
+        Some(header) => header,
+        None => anyhow::bail!("no \"Provas de Ingresso\" header"),
+    };
+    let lines = section_lines(&mut header.next_siblings());
+    let exams = parse_exams(lines.iter().map(String::as_str))?;
+    let requirement = ExamRequirement::from(&exams);
+    Ok(format!("{}\ndnf: {}", exams, requirement.to_dnf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corpus_passes() {
+        let cases =
+            check_corpus(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/exams")).unwrap();
+        assert!(!cases.is_empty());
+        for case in cases {
+            assert_eq!(case.expected, case.actual, "{}", case.name);
+        }
+    }
+}
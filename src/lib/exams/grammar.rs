@@ -0,0 +1,196 @@
+//! ```text
+//! section := term ( "e"? term )*
+//! term    := exam
+//!          | "Uma das seguintes provas:" exam ( "ou"? exam )*
+//!          | "Um dos seguintes conjuntos:" set ( "ou" set )*
+//! set     := exam ( "e"? exam )*
+//! ```
+//!
+//! An "e" inside a choice is ambiguous: it could add an exam to the last set or the next term to
+//! the section. It continues the set when an exam follows it, and ends the choice otherwise.
+//! Likewise, exams right after a choice belong to it, never to the section.
+
+use thiserror::Error;
+
+use super::tokenizer::{Token, TokenKind};
+use super::types::{Exam, ExamGroup, Exams, ParseExamError};
+use crate::lib::utils::non_empty_vector::NonEmptyVector;
+
+const EXPECTED_TERM: &str =
+    "an exam, \"Uma das seguintes provas:\" or \"Um dos seguintes conjuntos:\"";
+const EXPECTED_EXAM: &str = "an exam";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseExamsError {
+    #[error("line {position}: expected {expected}, found \"{text}\"")]
+    Unexpected {
+        position: usize,
+        text: String,
+        expected: &'static str,
+    },
+    #[error("line {position}: bad exam \"{text}\": {error}")]
+    BadExam {
+        position: usize,
+        text: String,
+        error: ParseExamError,
+    },
+    #[error("expected {expected}, found the end of the section")]
+    UnexpectedEnd { expected: &'static str },
+}
+
+impl ParseExamsError {
+    /// The line the error is about, if there's one
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ParseExamsError::Unexpected { text, .. } | ParseExamsError::BadExam { text, .. } => {
+                Some(text)
+            }
+            ParseExamsError::UnexpectedEnd { .. } => None,
+        }
+    }
+}
+
+enum Term {
+    /// Has to be taken
+    Exam(Exam),
+    /// Any one of the groups has to be taken
+    Choice(NonEmptyVector<ExamGroup>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+pub(crate) fn parse(tokens: Vec<Token>) -> Result<Exams, ParseExamsError> {
+    let mut parser = Parser { tokens, next: 0 };
+    let mut mandatory = Vec::new();
+    let mut optional = Vec::new();
+
+    while parser.peek().is_some() {
+        match parser.term()? {
+            Term::Exam(exam) => mandatory.push(exam),
+            Term::Choice(choice) => optional.push(choice),
+        }
+        // terms one after the other, without an "e", are all required too
+        if let Some(TokenKind::And) = parser.peek() {
+            parser.bump();
+            if parser.peek().is_none() {
+                return Err(ParseExamsError::UnexpectedEnd {
+                    expected: EXPECTED_TERM,
+                });
+            }
+        }
+    }
+
+    Ok(Exams {
+        optional: NonEmptyVector::from_vec(optional).map(Into::into),
+        mandatory: NonEmptyVector::from_vec(mandatory).map(Into::into),
+    })
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&TokenKind> {
+        self.tokens.get(self.next + offset).map(|token| &token.kind)
+    }
+
+    fn bump(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn next_is_exam(&self, offset: usize) -> bool {
+        matches!(
+            self.peek_at(offset),
+            Some(TokenKind::Exam(_)) | Some(TokenKind::BadExam(_))
+        )
+    }
+
+    fn term(&mut self) -> Result<Term, ParseExamsError> {
+        let token = self.bump().ok_or(ParseExamsError::UnexpectedEnd {
+            expected: EXPECTED_TERM,
+        })?;
+        match token.kind {
+            TokenKind::Exam(exam) => Ok(Term::Exam(exam)),
+            TokenKind::OneOf => self.one_of().map(Term::Choice),
+            TokenKind::OneSetOf => self.one_set_of().map(Term::Choice),
+            TokenKind::BadExam(error) => Err(ParseExamsError::BadExam {
+                position: token.position,
+                text: token.text,
+                error,
+            }),
+            _ => Err(ParseExamsError::Unexpected {
+                position: token.position,
+                text: token.text,
+                expected: EXPECTED_TERM,
+            }),
+        }
+    }
+
+    /// Any one of a list of exams
+    fn one_of(&mut self) -> Result<NonEmptyVector<ExamGroup>, ParseExamsError> {
+        let mut groups = NonEmptyVector::new(ExamGroup::from(vec![self.exam()?]));
+        loop {
+            if let Some(TokenKind::Or) = self.peek() {
+                if self.next_is_exam(1) {
+                    self.bump();
+                }
+            }
+            if !self.next_is_exam(0) {
+                break;
+            }
+            groups.push(vec![self.exam()?].into());
+        }
+        Ok(groups)
+    }
+
+    /// Any one of a list of sets of exams
+    fn one_set_of(&mut self) -> Result<NonEmptyVector<ExamGroup>, ParseExamsError> {
+        let mut groups = NonEmptyVector::new(self.set()?);
+        while let Some(TokenKind::Or) = self.peek() {
+            self.bump();
+            groups.push(self.set()?);
+        }
+        Ok(groups)
+    }
+
+    fn set(&mut self) -> Result<ExamGroup, ParseExamsError> {
+        let mut exams = vec![self.exam()?];
+        loop {
+            if let Some(TokenKind::And) = self.peek() {
+                if self.next_is_exam(1) {
+                    self.bump();
+                }
+            }
+            if !self.next_is_exam(0) {
+                break;
+            }
+            exams.push(self.exam()?);
+        }
+        Ok(exams.into())
+    }
+
+    fn exam(&mut self) -> Result<Exam, ParseExamsError> {
+        let token = self.bump().ok_or(ParseExamsError::UnexpectedEnd {
+            expected: EXPECTED_EXAM,
+        })?;
+        match token.kind {
+            TokenKind::Exam(exam) => Ok(exam),
+            TokenKind::BadExam(error) => Err(ParseExamsError::BadExam {
+                position: token.position,
+                text: token.text,
+                error,
+            }),
+            _ => Err(ParseExamsError::Unexpected {
+                position: token.position,
+                text: token.text,
+                expected: EXPECTED_EXAM,
+            }),
+        }
+    }
+}
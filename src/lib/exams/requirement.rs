@@ -0,0 +1,292 @@
+//! Which combinations of exams a course accepts, as a boolean expression.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Display;
+
+use super::types::{Exam, ExamCode, Exams};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ExamRequirement {
+    /// Every one of them. Empty means there's nothing to take
+    All(Vec<ExamRequirement>),
+    /// Any one of them. Empty means nothing satisfies it
+    Any(Vec<ExamRequirement>),
+    Exam(ExamCode),
+}
+
+impl ExamRequirement {
+    /// Flattens nested `All`s and unwraps a single requirement
+    pub fn all(requirements: impl IntoIterator<Item = ExamRequirement>) -> Self {
+        let mut flat = Vec::new();
+        for requirement in requirements {
+            match requirement {
+                ExamRequirement::All(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        if flat.len() == 1 {
+            flat.pop().unwrap()
+        } else {
+            ExamRequirement::All(flat)
+        }
+    }
+
+    /// Flattens nested `Any`s and unwraps a single requirement
+    pub fn any(requirements: impl IntoIterator<Item = ExamRequirement>) -> Self {
+        let mut flat = Vec::new();
+        for requirement in requirements {
+            match requirement {
+                ExamRequirement::Any(inner) => flat.extend(inner),
+                other => flat.push(other),
+            }
+        }
+        if flat.len() == 1 {
+            flat.pop().unwrap()
+        } else {
+            ExamRequirement::Any(flat)
+        }
+    }
+
+    pub fn satisfied_by(&self, exams: &HashSet<ExamCode>) -> bool {
+        match self {
+            ExamRequirement::All(requirements) => requirements
+                .iter()
+                .all(|requirement| requirement.satisfied_by(exams)),
+            ExamRequirement::Any(requirements) => requirements
+                .iter()
+                .any(|requirement| requirement.satisfied_by(exams)),
+            ExamRequirement::Exam(code) => exams.contains(code),
+        }
+    }
+
+    /// Every acceptable combination of exams, without the ones that have another combination
+    /// inside them, sorted.
+    ///
+    /// There are no negations, so this is the same for any two equivalent requirements.
+    pub fn dnf(&self) -> Vec<BTreeSet<ExamCode>> {
+        let combinations = match self {
+            ExamRequirement::Exam(code) => vec![BTreeSet::from([code.clone()])],
+            ExamRequirement::Any(requirements) => {
+                requirements.iter().flat_map(ExamRequirement::dnf).collect()
+            }
+            ExamRequirement::All(requirements) => requirements.iter().fold(
+                vec![BTreeSet::<ExamCode>::new()],
+                |combinations, requirement| {
+                    let options = requirement.dnf();
+                    combinations
+                        .iter()
+                        .flat_map(|combination| {
+                            options
+                                .iter()
+                                .map(move |option| combination.union(option).cloned().collect())
+                        })
+                        .collect()
+                },
+            ),
+        };
+        minimal(combinations)
+    }
+
+    /// Whether both accept exactly the same combinations of exams
+    pub fn equivalent(&self, other: &ExamRequirement) -> bool {
+        self.dnf() == other.dnf()
+    }
+
+    /// The requirement as a list of acceptable combinations, `(02 and 16) or (02 and 19)`
+    pub fn to_dnf(&self) -> ExamRequirement {
+        ExamRequirement::from_combinations(self.dnf())
+    }
+
+    /// Any one of the combinations
+    pub fn from_combinations(combinations: Vec<BTreeSet<ExamCode>>) -> ExamRequirement {
+        ExamRequirement::any(combinations.into_iter().map(|combination| {
+            ExamRequirement::all(combination.into_iter().map(ExamRequirement::Exam))
+        }))
+    }
+}
+
+fn minimal(mut combinations: Vec<BTreeSet<ExamCode>>) -> Vec<BTreeSet<ExamCode>> {
+    combinations.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    combinations.dedup();
+    let mut kept: Vec<BTreeSet<ExamCode>> = Vec::new();
+    for combination in combinations {
+        if !kept.iter().any(|smaller| smaller.is_subset(&combination)) {
+            kept.push(combination);
+        }
+    }
+    kept.sort();
+    kept
+}
+
+impl From<&Exam> for ExamRequirement {
+    fn from(exam: &Exam) -> Self {
+        match exam.code {
+            Some(ref code) => ExamRequirement::Exam(code.clone()),
+            // can't tell which exam it is, so nothing satisfies it
+            None => ExamRequirement::Any(vec![]),
+        }
+    }
+}
+
+impl From<&Exams> for ExamRequirement {
+    fn from(exams: &Exams) -> Self {
+        let mandatory = exams
+            .mandatory
+            .iter()
+            .flat_map(|mandatory| mandatory.iter())
+            .map(ExamRequirement::from);
+        let choices = exams
+            .optional
+            .iter()
+            .flat_map(|optional| optional.iter())
+            .map(|choice| {
+                let groups = choice
+                    .iter()
+                    .map(|group| ExamRequirement::all(group.iter().map(ExamRequirement::from)));
+                ExamRequirement::any(groups)
+            });
+        ExamRequirement::all(mandatory.chain(choices))
+    }
+}
+
+/// `02 and (16 or 19)`
+impl Display for ExamRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (requirements, separator) = match self {
+            ExamRequirement::Exam(code) => return write!(f, "{}", code),
+            ExamRequirement::All(requirements) if requirements.is_empty() => {
+                return write!(f, "none")
+            }
+            ExamRequirement::Any(requirements) if requirements.is_empty() => {
+                return write!(f, "nothing")
+            }
+            ExamRequirement::All(requirements) => (requirements, " and "),
+            ExamRequirement::Any(requirements) => (requirements, " or "),
+        };
+        for (i, requirement) in requirements.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", separator)?;
+            }
+            match requirement {
+                ExamRequirement::Exam(_) => write!(f, "{}", requirement)?,
+                _ => write!(f, "({})", requirement)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exam(code: &str) -> ExamRequirement {
+        ExamRequirement::Exam(code.into())
+    }
+
+    fn taken(codes: &[&str]) -> HashSet<ExamCode> {
+        codes.iter().map(|&code| code.into()).collect()
+    }
+
+    fn combinations(combinations: &[&[&str]]) -> Vec<BTreeSet<ExamCode>> {
+        combinations
+            .iter()
+            .map(|combination| combination.iter().map(|&code| code.into()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn satisfied_by() {
+        // 19 and (04 or 07)
+        let requirement =
+            ExamRequirement::all([exam("19"), ExamRequirement::any([exam("04"), exam("07")])]);
+        assert!(requirement.satisfied_by(&taken(&["19", "07"])));
+        assert!(requirement.satisfied_by(&taken(&["04", "19", "02"])));
+        assert!(!requirement.satisfied_by(&taken(&["19"])));
+        assert!(!requirement.satisfied_by(&taken(&["04", "07"])));
+        assert!(!requirement.satisfied_by(&taken(&[])));
+    }
+
+    #[test]
+    fn dnf_drops_combinations_with_another_inside() {
+        // (02 and 07 and 19) or (02 and 07)
+        let requirement = ExamRequirement::any([
+            ExamRequirement::all([exam("02"), exam("07"), exam("19")]),
+            ExamRequirement::all([exam("02"), exam("07")]),
+        ]);
+        assert_eq!(requirement.dnf(), combinations(&[&["02", "07"]]));
+
+        // (02 or 19) and 19
+        let requirement =
+            ExamRequirement::all([ExamRequirement::any([exam("02"), exam("19")]), exam("19")]);
+        assert_eq!(requirement.dnf(), combinations(&[&["19"]]));
+    }
+
+    #[test]
+    fn dnf_multiplies_out_choices() {
+        // ((02 and 07) or 19) and (04 or 07)
+        let requirement = ExamRequirement::all([
+            ExamRequirement::any([ExamRequirement::all([exam("02"), exam("07")]), exam("19")]),
+            ExamRequirement::any([exam("04"), exam("07")]),
+        ]);
+        assert_eq!(
+            requirement.dnf(),
+            combinations(&[&["02", "07"], &["04", "19"], &["07", "19"]])
+        );
+        assert_eq!(
+            requirement.to_dnf().to_string(),
+            "(02 and 07) or (04 and 19) or (07 and 19)"
+        );
+    }
+
+    #[test]
+    fn differently_nested_requirements_are_equivalent() {
+        // 02 and (16 or 19)
+        let factored =
+            ExamRequirement::all([exam("02"), ExamRequirement::any([exam("16"), exam("19")])]);
+        // (02 and 16) or (19 and 02)
+        let expanded = ExamRequirement::any([
+            ExamRequirement::all([exam("02"), exam("16")]),
+            ExamRequirement::all([exam("19"), exam("02")]),
+        ]);
+        // (02 and (16)) or ((02) and 19), without the constructors flattening anything
+        let nested = ExamRequirement::Any(vec![
+            ExamRequirement::All(vec![exam("02"), ExamRequirement::Any(vec![exam("16")])]),
+            ExamRequirement::All(vec![ExamRequirement::All(vec![exam("02")]), exam("19")]),
+        ]);
+        assert!(factored.equivalent(&expanded));
+        assert!(expanded.equivalent(&nested));
+        assert!(!factored.equivalent(&exam("02")));
+        assert!(!factored.equivalent(&ExamRequirement::all([exam("02"), exam("16"), exam("19")])));
+    }
+
+    #[test]
+    fn exam_without_a_code_is_never_satisfied() {
+        let requirement = ExamRequirement::from(&Exam {
+            code: None,
+            name: None,
+        });
+        assert_eq!(requirement, ExamRequirement::Any(vec![]));
+        assert_eq!(requirement.to_string(), "nothing");
+        assert!(!requirement.satisfied_by(&taken(&[])));
+        assert!(!requirement.satisfied_by(&taken(&["02", "07", "19"])));
+        assert!(requirement.dnf().is_empty());
+
+        // and it sinks whatever it's part of, unless there's another way in
+        let with_code = ExamRequirement::all([exam("19"), requirement.clone()]);
+        assert!(!with_code.satisfied_by(&taken(&["19"])));
+        assert!(with_code.dnf().is_empty());
+        let or_code = ExamRequirement::any([exam("19"), requirement]);
+        assert!(or_code.satisfied_by(&taken(&["19"])));
+        assert_eq!(or_code.dnf(), combinations(&[&["19"]]));
+    }
+
+    #[test]
+    fn empty_all_is_always_satisfied() {
+        let requirement = ExamRequirement::All(vec![]);
+        assert_eq!(requirement.to_string(), "none");
+        assert!(requirement.satisfied_by(&taken(&[])));
+        assert_eq!(requirement.dnf(), combinations(&[&[]]));
+        assert_eq!(requirement.to_dnf(), ExamRequirement::All(vec![]));
+    }
+}
@@ -0,0 +1,63 @@
+use super::types::{Exam, ParseExamError};
+
+/// One line of the "Provas de Ingresso" section
+#[derive(Debug, Clone)]
+pub(crate) enum TokenKind {
+    /// "Uma das seguintes provas:"
+    OneOf,
+    /// "Um dos seguintes conjuntos:"
+    OneSetOf,
+    /// "e"
+    And,
+    /// "ou"
+    Or,
+    /// "19 Matemática A"
+    Exam(Exam),
+    /// A line that starts with a code but isn't an exam
+    BadExam(ParseExamError),
+    /// Anything else
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    /// Line of the section the token is on, starting at 1 and counting blank lines
+    pub(crate) position: usize,
+    /// The line, after whitespace normalization
+    pub(crate) text: String,
+}
+
+/// Turns the lines of the section into tokens, skipping blank ones
+pub(crate) fn tokenize<'a>(lines: impl IntoIterator<Item = &'a str>) -> Vec<Token> {
+    lines
+        .into_iter()
+        .map(normalize_whitespace)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, text)| Token {
+            kind: token_kind(&text),
+            position: i + 1,
+            text,
+        })
+        .collect()
+}
+
+fn token_kind(line: &str) -> TokenKind {
+    match line.to_lowercase().as_str() {
+        "uma das seguintes provas:" => TokenKind::OneOf,
+        "um dos seguintes conjuntos:" => TokenKind::OneSetOf,
+        "e" => TokenKind::And,
+        "ou" => TokenKind::Or,
+        _ if line.starts_with(|c: char| c.is_ascii_digit()) => match line.parse::<Exam>() {
+            Ok(exam) => TokenKind::Exam(exam),
+            Err(err) => TokenKind::BadExam(err),
+        },
+        _ => TokenKind::Text,
+    }
+}
+
+/// NBSPs, tabs and newlines become plain spaces, runs of them a single one
+pub(crate) fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
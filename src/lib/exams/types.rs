@@ -1,15 +1,19 @@
+use std::fmt::Display;
 use std::str::FromStr;
 
+use thiserror::Error;
+
+use super::requirement::ExamRequirement;
 use crate::lib::utils::non_empty_vector::NonEmptyVector;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct Exam {
     pub code: Option<ExamCode>,
     pub name: Option<ExamName>,
 }
 
 // newtype
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ExamCode(String);
 impl From<&str> for ExamCode {
     fn from(exam: &str) -> Self {
@@ -23,8 +27,14 @@ impl From<ExamCode> for String {
     }
 }
 
+impl Display for ExamCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 // newtype
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ExamName(String);
 impl From<&str> for ExamName {
     fn from(exam: &str) -> Self {
@@ -42,6 +52,12 @@ impl From<ExamName> for String {
 #[derive(Debug, Clone, Default)]
 pub struct ExamGroup(Vec<Exam>);
 
+impl ExamGroup {
+    pub fn iter(&self) -> std::slice::Iter<'_, Exam> {
+        self.0.iter()
+    }
+}
+
 impl IntoIterator for ExamGroup {
     type Item = Exam;
 
@@ -84,6 +100,13 @@ pub struct Exams {
 #[derive(Debug)]
 pub struct OptionalExams(NonEmptyVector<NonEmptyVector<ExamGroup>>);
 
+impl OptionalExams {
+    /// Every choice has to be satisfied, by any one of its groups
+    pub fn iter(&self) -> std::slice::Iter<'_, NonEmptyVector<ExamGroup>> {
+        self.0.iter()
+    }
+}
+
 impl IntoIterator for OptionalExams {
     type Item = NonEmptyVector<ExamGroup>;
 
@@ -116,6 +139,12 @@ impl From<OptionalExams> for Vec<NonEmptyVector<ExamGroup>> {
 #[derive(Debug)]
 pub struct MandatoryExams(NonEmptyVector<Exam>);
 
+impl MandatoryExams {
+    pub fn iter(&self) -> std::slice::Iter<'_, Exam> {
+        self.0.iter()
+    }
+}
+
 impl IntoIterator for MandatoryExams {
     type Item = Exam;
 
@@ -145,25 +174,41 @@ impl From<MandatoryExams> for Vec<Exam> {
     }
 }
 
+/// `19 and (04 or 07)`, with exam codes only
+impl Display for Exams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", ExamRequirement::from(self))
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseExamError {
+    #[error("expected \"<code> <name>\"")]
+    MissingName,
+    #[error("exam code \"{0}\" isn't a number")]
+    BadCode(String),
+}
+
 impl FromStr for Exam {
-    //TODO: do this
-    type Err = ();
+    type Err = ParseExamError;
 
-    //? should do some checks maybe
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        return match s.split_once(" ") {
-            Some((code, name)) => Ok(Exam {
-                code: Some(code.trim().into()),
-                name: Some(name.trim().into()),
-            }),
-            None => Err(()),
+        let (code, name) = match s.trim().split_once(" ") {
+            Some((code, name)) if !name.trim().is_empty() => (code.trim(), name.trim()),
+            _ => return Err(ParseExamError::MissingName),
         };
+        if !code.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ParseExamError::BadCode(code.to_string()));
+        }
+        Ok(Exam {
+            code: Some(code.into()),
+            name: Some(name.into()),
+        })
     }
 }
 
 impl TryInto<Exam> for &str {
-    //TODO: do this
-    type Error = ();
+    type Error = ParseExamError;
 
     fn try_into(self) -> Result<Exam, Self::Error> {
         self.parse::<Exam>()
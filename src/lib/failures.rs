@@ -0,0 +1,41 @@
+use std::sync::{Arc, Mutex};
+
+use reqwest::Url;
+
+use super::MyScraperState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FailureKind {
+    /// The request itself failed (connection reset, timeout, ...)
+    Fetch,
+    /// The server answered with a non-success status
+    Status,
+    /// The page was fetched but couldn't be turned into something we can store
+    Parse,
+    /// The server answered with its error page instead of the page we asked for
+    ErrorPage,
+}
+
+impl FailureKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            FailureKind::Fetch => "fetch",
+            FailureKind::Status => "status",
+            FailureKind::Parse => "parse",
+            FailureKind::ErrorPage => "error_page",
+        }
+    }
+}
+
+/// A page that couldn't be fetched or parsed, even after retrying
+#[derive(Debug, Clone)]
+pub(super) struct FailedPage {
+    pub(super) url: Url,
+    pub(super) state: Option<MyScraperState>,
+    pub(super) kind: FailureKind,
+    pub(super) attempts: u32,
+    pub(super) error: String,
+}
+
+/// Failures the http client gives up on, shared between the client and `handle_results`
+pub(super) type FailedPages = Arc<Mutex<Vec<FailedPage>>>;
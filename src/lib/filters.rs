@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+
+/// Narrows a crawl down to part of the site.
+///
+/// Letters and institution codes prune the crawl frontier, before any request is made. The CNAEF
+/// area is only known once the course page is scraped, so it's checked before the course is saved.
+#[derive(Debug, Clone, Default)]
+pub struct CrawlFilter {
+    /// Letter index pages to follow (`letra=`). All of them, if empty
+    pub letters: HashSet<String>,
+    /// Institution code patterns, `*` matches anything (`03*`). All institutions, if empty
+    pub institutions: Vec<String>,
+    /// CNAEF areas of any level, everything under them (`4`, `48` and `481` all match `481`).
+    /// All areas, if empty
+    pub cnaef_areas: Vec<String>,
+    /// Stop queueing courses after this many
+    pub max_courses: Option<usize>,
+    /// Crawl a random subset of this many courses
+    pub sample: Option<usize>,
+}
+
+impl CrawlFilter {
+    pub fn allows_letter(&self, letter: &str) -> bool {
+        self.letters.is_empty() || self.letters.contains(&letter.to_uppercase())
+    }
+
+    pub fn allows_institution(&self, institution_code: &str) -> bool {
+        self.institutions.is_empty()
+            || self
+                .institutions
+                .iter()
+                .any(|pattern| glob_matches(pattern, institution_code))
+    }
+
+    /// Leaves part of the site out, so the crawl can't tell which courses a guide doesn't have
+    pub fn is_partial(&self) -> bool {
+        !self.letters.is_empty()
+            || !self.institutions.is_empty()
+            || !self.cnaef_areas.is_empty()
+            || self.max_courses.is_some()
+            || self.sample.is_some()
+    }
+
+    pub fn allows_cnaef_area(&self, cnaef_area_code: Option<&str>) -> bool {
+        if self.cnaef_areas.is_empty() {
+            return true;
+        }
+        match cnaef_area_code {
+            Some(code) => self
+                .cnaef_areas
+                .iter()
+                .any(|prefix| code.starts_with(prefix.as_str())),
+            None => false,
+        }
+    }
+}
+
+/// Matches `text` against a pattern where `*` stands for any (possibly empty) sequence
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => match text.strip_prefix(prefix) {
+            Some(text) => (0..=text.len())
+                .filter(|i| text.is_char_boundary(*i))
+                .any(|i| glob_matches(rest, &text[i..])),
+            None => false,
+        },
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use voyager::scraper::{ElementRef, Html, Node, Selector};
+
+/// What to do when the pages don't look like the baseline anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriftMode {
+    Warn,
+    Fail,
+}
+
+impl FromStr for DriftMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(DriftMode::Warn),
+            "fail" => Ok(DriftMode::Fail),
+            mode => Err(format!("unknown drift mode: \"{}\"", mode)),
+        }
+    }
+}
+
+/// Compares a run's layout against a saved baseline
+#[derive(Debug, Clone)]
+pub struct LayoutCheck {
+    pub baseline: PathBuf,
+    pub mode: DriftMode,
+    /// Saves the run's layout as the new baseline instead of comparing
+    pub update: bool,
+}
+
+impl LayoutCheck {
+    /// `partial` runs only visit part of the site, so what they didn't see isn't drift
+    pub fn check(&self, layout: &LayoutFingerprint, partial: bool) -> Result<()> {
+        if layout.pages.is_empty() {
+            return Ok(());
+        }
+        if self.update {
+            info!("Saving the layout baseline to {}", self.baseline.display());
+            return layout.save(&self.baseline);
+        }
+        if !self.baseline.exists() {
+            let message = format!(
+                "No layout baseline at {}, rerun with --update-layout-baseline to create one",
+                self.baseline.display()
+            );
+            if self.mode == DriftMode::Fail {
+                bail!(message);
+            }
+            warn!("{}", message);
+            return Ok(());
+        }
+
+        let drift = layout.drift(&LayoutFingerprint::load(&self.baseline)?, partial);
+        for change in drift.iter() {
+            warn!("LAYOUT DRIFT: {}", change);
+        }
+        if !drift.is_empty() && self.mode == DriftMode::Fail {
+            bail!(
+                "The pages don't look like {} anymore ({} changes). Fix the scraper, or rerun with --update-layout-baseline if the changes are harmless",
+                self.baseline.display(),
+                drift.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The structure of one kind of page, as seen over a whole run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageFingerprint {
+    /// Section headers, with digits masked so a new year isn't a new layout
+    pub headers: BTreeSet<String>,
+    /// Names of the `Field: value` lines
+    pub fields: BTreeSet<String>,
+    /// DOM paths of the elements each of our selectors matched
+    pub selectors: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl PageFingerprint {
+    pub(super) fn header(&mut self, header: &str) {
+        self.headers.insert(mask_digits(header));
+    }
+
+    /// Picks up the `Field: value` lines that follow a section header
+    pub(super) fn fields_after(&mut self, header: ElementRef) {
+        for node in header.next_siblings() {
+            match node.value() {
+                Node::Text(text) => match text.split_once(": ") {
+                    Some((field, _value)) => {
+                        self.fields.insert(field.trim().to_string());
+                    }
+                    None => break,
+                },
+                Node::Element(element) if element.name() == "br" => {}
+                _ => break,
+            }
+        }
+    }
+
+    pub(super) fn selector(&mut self, name: &str, html: &Html, selector: &Selector) {
+        let paths = self.selectors.entry(name.to_string()).or_default();
+        paths.extend(html.select(selector).map(dom_path));
+    }
+
+    fn merge(&mut self, other: PageFingerprint) {
+        self.headers.extend(other.headers);
+        self.fields.extend(other.fields);
+        for (name, paths) in other.selectors {
+            self.selectors.entry(name).or_default().extend(paths);
+        }
+    }
+
+    /// Only what's new if `partial`
+    fn drift(
+        &self,
+        baseline: &PageFingerprint,
+        page: &str,
+        partial: bool,
+        drift: &mut Vec<String>,
+    ) {
+        for header in self.headers.difference(&baseline.headers) {
+            drift.push(format!("{}: new header \"{}\"", page, header));
+        }
+        if !partial {
+            for header in baseline.headers.difference(&self.headers) {
+                drift.push(format!("{}: header \"{}\" never showed up", page, header));
+            }
+        }
+        for field in self.fields.difference(&baseline.fields) {
+            drift.push(format!("{}: new field \"{}\"", page, field));
+        }
+        if !partial {
+            for field in baseline.fields.difference(&self.fields) {
+                drift.push(format!("{}: field \"{}\" never showed up", page, field));
+            }
+        }
+        for (name, baseline_paths) in baseline.selectors.iter() {
+            let paths = self.selectors.get(name).cloned().unwrap_or_default();
+            if paths.is_empty() && !baseline_paths.is_empty() && !partial {
+                drift.push(format!("{}: selector {} matched nothing", page, name));
+            }
+            for path in paths.difference(baseline_paths) {
+                drift.push(format!("{}: selector {} matched {}", page, name, path));
+            }
+        }
+    }
+}
+
+/// Fingerprints of every kind of page a run went through (`index`, `letter`, `course`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayoutFingerprint {
+    pub pages: BTreeMap<String, PageFingerprint>,
+}
+
+pub(super) type SharedLayoutFingerprint = Arc<Mutex<LayoutFingerprint>>;
+
+impl LayoutFingerprint {
+    pub(super) fn record(&mut self, page: &str, fingerprint: PageFingerprint) {
+        self.pages
+            .entry(page.to_string())
+            .or_default()
+            .merge(fingerprint);
+    }
+
+    /// Everything that changed since the baseline, one line each.
+    ///
+    /// Kinds of page this run didn't visit (a run from a csv file never sees the index) are left
+    /// out. Optional sections only show up on some courses, so for `partial` runs only what's new
+    /// counts.
+    pub fn drift(&self, baseline: &LayoutFingerprint, partial: bool) -> Vec<String> {
+        let mut drift = Vec::new();
+        for (page, fingerprint) in self.pages.iter() {
+            match baseline.pages.get(page) {
+                Some(baseline) => fingerprint.drift(baseline, page, partial, &mut drift),
+                None => drift.push(format!("{}: not in the baseline", page)),
+            }
+        }
+        drift
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// `html > body > div#caixa-orange > div.cab1`
+fn dom_path(element: ElementRef) -> String {
+    let mut path: Vec<String> = element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .chain(std::iter::once(element))
+        .map(|element| {
+            let element = element.value();
+            let mut step = element.name().to_string();
+            if let Some(id) = element.id() {
+                step.push('#');
+                step.push_str(id);
+            }
+            let mut classes: Vec<&str> = element.classes().collect();
+            classes.sort_unstable();
+            for class in classes {
+                step.push('.');
+                step.push_str(class);
+            }
+            step
+        })
+        .collect();
+    // ancestors go from the parent up, the element itself is already last
+    let element = path.pop();
+    path.reverse();
+    path.extend(element);
+    path.join(" > ")
+}
+
+fn mask_digits(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_ascii_digit() { '#' } else { c })
+        .collect()
+}
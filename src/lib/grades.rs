@@ -0,0 +1,186 @@
+//! Grades on the 0-200 scale DGES uses, and the "Classificações Mínimas" and "Fórmula de Cálculo"
+//! sections.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use ego_tree::NodeRef;
+use serde::Deserialize;
+use thiserror::Error;
+use voyager::scraper::Node;
+
+use super::diagnostics::ParseDiagnostic;
+use super::unrecognized::UnrecognizedContent;
+
+/// Points, from 0 to 200
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Deserialize)]
+#[serde(try_from = "f64")]
+pub struct Grade(f64);
+
+impl Grade {
+    pub const MAX: f64 = 200.0;
+
+    pub fn points(self) -> f64 {
+        self.0
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseGradeError {
+    #[error("\"{0}\" isn't a number of points")]
+    NotANumber(String),
+    #[error("{0} is outside 0-200")]
+    OutOfRange(f64),
+}
+
+impl TryFrom<f64> for Grade {
+    type Error = ParseGradeError;
+
+    fn try_from(points: f64) -> Result<Self, Self::Error> {
+        if (0.0..=Grade::MAX).contains(&points) {
+            Ok(Grade(points))
+        } else {
+            Err(ParseGradeError::OutOfRange(points))
+        }
+    }
+}
+
+/// `95 pontos`, `95,5 pontos` or just the number
+impl FromStr for Grade {
+    type Err = ParseGradeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let number = s.trim().trim_end_matches("pontos").trim();
+        match number.replace(',', ".").parse::<f64>() {
+            Ok(points) => points.try_into(),
+            Err(_) => Err(ParseGradeError::NotANumber(s.trim().to_string())),
+        }
+    }
+}
+
+impl Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The lowest grades a candidate can apply with
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct MinimumGrades {
+    pub(crate) application: Option<Grade>,
+    /// Applies to each entrance exam on its own
+    pub(crate) exams: Option<Grade>,
+}
+
+pub(crate) fn minimum_grades_section<'a>(
+    it: &mut impl Iterator<Item = NodeRef<'a, Node>>,
+    unrecognized: &mut Vec<UnrecognizedContent>,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> MinimumGrades {
+    let mut minimum_grades = MinimumGrades::default();
+    for_each_field(it, |node, field, value| {
+        let grade = match field {
+            "Nota de candidatura" => &mut minimum_grades.application,
+            "Provas de ingresso" => &mut minimum_grades.exams,
+            _ => {
+                unrecognized.push(UnrecognizedContent::field(node, field, value));
+                return;
+            }
+        };
+        match value.parse::<Grade>() {
+            Ok(value) => *grade = Some(value),
+            Err(err) => {
+                diagnostics.push(ParseDiagnostic::new("minimum_grades", field, value, err));
+            }
+        }
+    });
+    minimum_grades
+}
+
+/// How the application grade is calculated, as percentages
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Formula {
+    pub(crate) secondary_average: Option<u8>,
+    /// The average of the exams in the combination the candidate applies with
+    pub(crate) exams: Option<u8>,
+}
+
+impl Formula {
+    /// `secondary_average` from 0 to 20, rounded to a tenth like DGES does. `None` if a weight is
+    /// missing
+    pub(crate) fn application_grade(
+        &self,
+        secondary_average: f64,
+        exams: &[Grade],
+    ) -> Option<Grade> {
+        let (secondary_weight, exams_weight) = (self.secondary_average?, self.exams?);
+        let exams_average = if exams.is_empty() {
+            0.0
+        } else {
+            exams.iter().map(|grade| grade.points()).sum::<f64>() / exams.len() as f64
+        };
+        let points = secondary_average * 10.0 * f64::from(secondary_weight) / 100.0
+            + exams_average * f64::from(exams_weight) / 100.0;
+        Grade::try_from((points * 10.0).round() / 10.0).ok()
+    }
+}
+
+pub(crate) fn formula_section<'a>(
+    it: &mut impl Iterator<Item = NodeRef<'a, Node>>,
+    unrecognized: &mut Vec<UnrecognizedContent>,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Formula {
+    let mut formula = Formula::default();
+    for_each_field(it, |node, field, value| {
+        let weight = match field {
+            "Média do secundário" => &mut formula.secondary_average,
+            "Provas de ingresso" => &mut formula.exams,
+            _ => {
+                unrecognized.push(UnrecognizedContent::field(node, field, value));
+                return;
+            }
+        };
+        match parse_percentage(value) {
+            Ok(value) => *weight = Some(value),
+            Err(err) => diagnostics.push(ParseDiagnostic::new("formula", field, value, err)),
+        }
+    });
+    if let (Some(secondary_average), Some(exams)) = (formula.secondary_average, formula.exams) {
+        if secondary_average + exams != 100 {
+            let weights = format!("{}% + {}%", secondary_average, exams);
+            diagnostics.push(ParseDiagnostic::new(
+                "formula",
+                "weights",
+                &weights,
+                "doesn't add up to 100%",
+            ));
+        }
+    }
+    formula
+}
+
+fn parse_percentage(value: &str) -> Result<u8> {
+    match value.trim().trim_end_matches('%').trim().parse::<u8>() {
+        Ok(percentage) if percentage <= 100 => Ok(percentage),
+        _ => bail!("\"{}\" isn't a percentage", value.trim()),
+    }
+}
+
+/// Calls `on_field` for each `Field: value` line, up to the end of the section
+fn for_each_field<'a>(
+    it: &mut impl Iterator<Item = NodeRef<'a, Node>>,
+    mut on_field: impl FnMut(NodeRef<'a, Node>, &str, &str),
+) {
+    for node in it {
+        match node.value() {
+            Node::Text(text) => match text.trim().split_once(": ") {
+                Some((field, value)) => on_field(node, field, value),
+                None if text.trim().is_empty() => {}
+                None => break,
+            },
+            Node::Element(element) if element.name() == "br" => {}
+            _ => break,
+        }
+    }
+}
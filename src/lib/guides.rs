@@ -0,0 +1,139 @@
+//! Each year DGES publishes a new guide. Pages say which one they belong to in their first header,
+//! and everything saved from them is kept per guide year so years can be compared.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use anyhow::{bail, Result};
+use diesel::SqliteConnection;
+
+use super::course_url::CourseUrl;
+use super::db::{get_complete_guide_years, get_guide_courses, get_guide_years};
+use super::eligibility::exam_requirements;
+use super::exams::ExamRequirement;
+
+const GUIDE_HEADER: &str = "Guia das Provas de Ingresso de ";
+
+/// `2022` in `Guia das Provas de Ingresso de 2022 - Detalhe de Curso<br>&nbsp;`
+pub(crate) fn guide_year(header: &str) -> Option<u16> {
+    let year = header.strip_prefix(GUIDE_HEADER)?.get(..4)?;
+    if year.chars().all(|c| c.is_ascii_digit()) {
+        year.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// `year`, or the most recent guide crawled if it's not given
+pub fn resolve_guide_year(conn: &mut SqliteConnection, year: Option<u16>) -> Result<i32> {
+    if let Some(year) = year {
+        return Ok(i32::from(year));
+    }
+    match get_guide_years(conn)?.first() {
+        Some(&year) => Ok(year),
+        None => bail!("No guide crawled yet"),
+    }
+}
+
+/// What changed from one guide to another
+pub struct GuideComparison {
+    pub from: i32,
+    pub to: i32,
+    /// With their name in the newer guide. `None` if no run saw every course of the older guide,
+    /// the courses it missed would look new
+    pub(crate) new_courses: Option<Vec<(CourseUrl, Option<String>)>>,
+    /// With their name in the older guide. `None` if no run saw every course of the newer guide,
+    /// the courses it missed would look closed
+    pub(crate) closed_courses: Option<Vec<(CourseUrl, Option<String>)>>,
+    /// Before and after, only for courses both guides have
+    pub(crate) changed_requirements: Vec<(CourseUrl, ExamRequirement, ExamRequirement)>,
+}
+
+impl GuideComparison {
+    pub fn is_empty(&self) -> bool {
+        self.new_courses.as_ref().map_or(true, Vec::is_empty)
+            && self.closed_courses.as_ref().map_or(true, Vec::is_empty)
+            && self.changed_requirements.is_empty()
+    }
+}
+
+pub fn compare_guides(conn: &mut SqliteConnection, from: i32, to: i32) -> Result<GuideComparison> {
+    let courses = |conn: &mut SqliteConnection, year| -> Result<BTreeMap<_, _>> {
+        Ok(get_guide_courses(conn, year)?
+            .into_iter()
+            .map(|row| (CourseUrl::new(&row.institution, &row.course), row.name))
+            .collect())
+    };
+    let (before, after) = (courses(conn, from)?, courses(conn, to)?);
+    let (mut requirements_before, requirements_after) =
+        (exam_requirements(conn, from)?, exam_requirements(conn, to)?);
+
+    let complete = get_complete_guide_years(conn)?;
+
+    let only_in = |guide: &BTreeMap<CourseUrl, Option<String>>, other: &BTreeMap<_, _>| {
+        guide
+            .iter()
+            .filter(|(course, _)| !other.contains_key(course))
+            .map(|(course, name)| (course.clone(), name.clone()))
+            .collect()
+    };
+    let new_courses = complete.contains(&from).then(|| only_in(&after, &before));
+    let closed_courses = complete.contains(&to).then(|| only_in(&before, &after));
+    let changed_requirements = requirements_after
+        .into_iter()
+        .filter_map(|(course, requirement)| {
+            let previous = requirements_before.remove(&course)?;
+            if previous.equivalent(&requirement) {
+                None
+            } else {
+                Some((course, previous, requirement))
+            }
+        })
+        .collect();
+
+    Ok(GuideComparison {
+        from,
+        to,
+        new_courses,
+        closed_courses,
+        changed_requirements,
+    })
+}
+
+fn course_line(course: &CourseUrl, name: &Option<String>) -> String {
+    match name {
+        Some(name) => format!("{} {}", course, name),
+        None => course.to_string(),
+    }
+}
+
+/// `+` for new courses, `-` for closed ones and `~` for changed exam requirements
+impl Display for GuideComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} -> {}", self.from, self.to)?;
+        for (course, name) in self.new_courses.iter().flatten() {
+            writeln!(f, "+ {}", course_line(course, name))?;
+        }
+        for (course, name) in self.closed_courses.iter().flatten() {
+            writeln!(f, "- {}", course_line(course, name))?;
+        }
+        for (course, before, after) in self.changed_requirements.iter() {
+            writeln!(f, "~ {}: {} -> {}", course, before, after)?;
+        }
+        if self.new_courses.is_none() {
+            writeln!(
+                f,
+                "warning: no crawl saw every course of {}, new courses aren't listed",
+                self.from
+            )?;
+        }
+        if self.closed_courses.is_none() {
+            writeln!(
+                f,
+                "warning: no crawl saw every course of {}, closed courses aren't listed",
+                self.to
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,240 @@
+//! A stand-in for the DGES website, serving fixture pages from a directory.
+//!
+//! Fixture layout:
+//! - `index.html` for `indcurso.asp`
+//! - `letters/<letra>.html` for `indcurso.asp?letra=<letra>`
+//! - `courses/<code>-<codc>.html` for `detcursopi.asp?codc=<codc>&code=<code>`
+//! - `robots.txt` (optional, everything is allowed if missing)
+//! - `course-not-found.html` (optional), served with a 200 for unknown courses, like DGES does
+//! - `error.html` (optional), the server error page served by `error` faults
+//!
+//! Fixtures should be Windows-1252 encoded, like the real pages.
+
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use rand::Rng;
+use tracing::info;
+
+use super::config::{DEFAULT_COURSE_PATH, DEFAULT_INDEX_PATH};
+
+/// Same content type DGES serves, without a charset. See `HtmlCharsetWindows1252`.
+const HTML_CONTENT_TYPE: &str = "text/html";
+
+#[derive(Debug, Clone, Copy)]
+pub enum Fault {
+    NotFound,
+    ServerError,
+    /// A server error page, served with a 200
+    ErrorPage,
+    Slow,
+}
+
+/// `KIND[@PROBABILITY][:PATTERN]`, e.g. `500@0.1` or `404:codc=9119`.
+///
+/// `KIND` is one of `404`, `500`, `error` or `slow`. The rule applies to requests whose path and query
+/// contain `PATTERN` (all of them, if missing), with the given probability (1 if missing).
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    pub fault: Fault,
+    pub probability: f64,
+    pub pattern: Option<String>,
+}
+
+impl FromStr for FaultRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, pattern) = match s.split_once(':') {
+            Some((rule, pattern)) => (rule, Some(pattern.to_string())),
+            None => (s, None),
+        };
+        let (kind, probability) = match rule.split_once('@') {
+            Some((kind, probability)) => match probability.parse::<f64>() {
+                Ok(probability) if (0.0..=1.0).contains(&probability) => (kind, probability),
+                _ => return Err(format!("bad probability: \"{}\"", probability)),
+            },
+            None => (rule, 1.0),
+        };
+        let fault = match kind {
+            "404" => Fault::NotFound,
+            "500" => Fault::ServerError,
+            "error" => Fault::ErrorPage,
+            "slow" => Fault::Slow,
+            kind => return Err(format!("unknown fault: \"{}\"", kind)),
+        };
+        Ok(FaultRule {
+            fault,
+            probability,
+            pattern,
+        })
+    }
+}
+
+impl FaultRule {
+    fn applies_to(&self, path_and_query: &str) -> bool {
+        let matches = match self.pattern {
+            Some(ref pattern) => path_and_query.contains(pattern.as_str()),
+            None => true,
+        };
+        matches && rand::thread_rng().gen_bool(self.probability)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MockServerConfig {
+    pub address: SocketAddr,
+    pub fixtures: PathBuf,
+    pub faults: Vec<FaultRule>,
+    /// How long a `slow` fault holds the response
+    pub slow_delay: Duration,
+}
+
+pub async fn serve(config: MockServerConfig) -> Result<()> {
+    if !config.fixtures.is_dir() {
+        bail!(
+            "fixtures directory doesn't exist: {}",
+            config.fixtures.display()
+        );
+    }
+
+    let address = config.address;
+    let config = Arc::new(config);
+    let make_service = make_service_fn(move |_connection| {
+        let config = config.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(config.clone(), request)
+            }))
+        }
+    });
+
+    info!("Mock DGES server listening on http://{}/", address);
+    Server::bind(&address).serve(make_service).await?;
+    Ok(())
+}
+
+async fn handle_request(
+    config: Arc<MockServerConfig>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    info!("{} {}", request.method(), path_and_query);
+
+    for rule in config.faults.iter() {
+        if rule.applies_to(path_and_query) {
+            match rule.fault {
+                Fault::NotFound => return Ok(status_response(StatusCode::NOT_FOUND)),
+                Fault::ServerError => {
+                    return Ok(status_response(StatusCode::INTERNAL_SERVER_ERROR))
+                }
+                Fault::ErrorPage => {
+                    return Ok(
+                        match tokio::fs::read(config.fixtures.join("error.html")).await {
+                            Ok(body) => html_response(body),
+                            Err(_) => status_response(StatusCode::INTERNAL_SERVER_ERROR),
+                        },
+                    )
+                }
+                Fault::Slow => tokio::time::sleep(config.slow_delay).await,
+            }
+        }
+    }
+
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let page = request.uri().path().rsplit('/').next().unwrap_or_default();
+    let fixture = match fixture_path(page, &query) {
+        Some(fixture) => config.fixtures.join(fixture),
+        None => return Ok(status_response(StatusCode::NOT_FOUND)),
+    };
+
+    Ok(match tokio::fs::read(&fixture).await {
+        Ok(body) if page == "robots.txt" => Response::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from(body))
+            .unwrap(),
+        Ok(body) => html_response(body),
+        // no robots.txt means everything is allowed
+        Err(_) if page == "robots.txt" => Response::builder()
+            .header(CONTENT_TYPE, "text/plain")
+            .body(Body::from("User-agent: *\nAllow: /\n"))
+            .unwrap(),
+        Err(_) if page == DEFAULT_COURSE_PATH => {
+            match tokio::fs::read(config.fixtures.join("course-not-found.html")).await {
+                Ok(body) => html_response(body),
+                Err(_) => status_response(StatusCode::NOT_FOUND),
+            }
+        }
+        Err(_) => status_response(StatusCode::NOT_FOUND),
+    })
+}
+
+/// Maps a DGES url to a file in the fixtures directory
+fn fixture_path(page: &str, query: &HashMap<String, String>) -> Option<PathBuf> {
+    // query values end up in a path, don't let them leave the fixtures directory
+    if !query
+        .values()
+        .all(|value| value.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        return None;
+    }
+
+    if page == DEFAULT_INDEX_PATH {
+        match query.get("letra") {
+            Some(letter) => Some(Path::new("letters").join(format!("{}.html", letter))),
+            None => Some("index.html".into()),
+        }
+    } else if page == DEFAULT_COURSE_PATH {
+        match (query.get("code"), query.get("codc")) {
+            (Some(institution_code), Some(course_code)) => Some(
+                Path::new("courses").join(format!("{}-{}.html", institution_code, course_code)),
+            ),
+            _ => None,
+        }
+    } else if page == "robots.txt" {
+        Some("robots.txt".into())
+    } else {
+        None
+    }
+}
+
+fn html_response(body: Vec<u8>) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, HTML_CONTENT_TYPE)
+        .body(Body::from(body))
+        .unwrap()
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, HTML_CONTENT_TYPE)
+        .body(Body::from(status.to_string()))
+        .unwrap()
+}
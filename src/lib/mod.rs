@@ -1,21 +1,41 @@
 use crate::lib::db::create_duration_unit;
 use crate::Record;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use characteristics::{characteristics_section, Characteristics};
+use config::{ClientConfig, SiteConfig};
+use course_url::{CourseUrl, EntrySource};
+use coverage::{Coverage, SharedCoverage};
+use diagnostics::ParseDiagnostic;
+use diesel::SqliteConnection;
 use diesel_migrations::embed_migrations;
 use diesel_migrations::EmbeddedMigrations;
 use ego_tree::NodeRef;
 use exams::{exams_section, Exams};
-use futures::StreamExt;
+use failures::{FailedPage, FailedPages, FailureKind};
+use filters::CrawlFilter;
+use fingerprint::{LayoutFingerprint, PageFingerprint, SharedLayoutFingerprint};
+use futures::{Stream, StreamExt};
+use grades::{formula_section, minimum_grades_section, Formula, Grade, MinimumGrades};
+use guides::guide_year;
+use rand::seq::SliceRandom;
 use reqwest::Url;
 use reqwest_middleware::ClientBuilder;
-use std::fmt::Debug;
+use statistics::{statistics_section, PhaseStatistics};
+use std::collections::{BTreeSet, HashMap};
 use std::result::Result::Ok;
-use tracing::info;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{info, warn};
+use unrecognized::UnrecognizedContent;
+use utils::cassette_middleware::{CassetteMiddleware, CassetteMode};
 use utils::charset_middleware::HtmlCharsetWindows1252;
+use utils::retry_middleware::{GaveUp, RetryMiddleware};
+use utils::robots::crawl_delay;
+use utils::throttle_middleware::ThrottleMiddleware;
 use voyager::scraper::Node;
 use voyager::scraper::{ElementRef, Selector};
-use voyager::{Collector, Crawler, CrawlerConfig, RequestDelay, Response, Scraper};
+use voyager::{Collector, Crawler, CrawlerConfig, Response, Scraper};
+use workload::{check_workload, Anomaly, Workload};
 
 use self::characteristics::institution;
 use self::characteristics::institution::Address;
@@ -24,90 +44,337 @@ use self::characteristics::institution::EmailAddressList;
 use self::characteristics::institution::PhoneNumber;
 use self::characteristics::institution::PhoneNumberList;
 use self::characteristics::Institution;
+use self::cnaef::{area_at, parent, Level};
 use self::db::create_duration;
 use self::db::create_institution;
-use self::db::create_main;
 use self::db::create_mandatory_exam;
-use self::db::{create_cnaef_area, create_exam, establish_connection};
+use self::db::{
+    create_anomaly, create_cnaef_area, create_course, create_course_area,
+    create_course_institution, create_exam, create_exam_requirement, create_failed_page,
+    create_formula, create_guide_course, create_minimum_grades, create_parse_diagnostic,
+    create_run, create_statistics, create_unrecognized_content, create_workload,
+    delete_exam_requirements, delete_failed_page, delete_mandatory_exams, get_failed_pages,
+    get_unrecognized_content, update_run,
+};
 use diesel_migrations::MigrationHarness;
 
 mod characteristics;
 
+pub mod application;
+pub mod cnaef;
+pub mod config;
+mod course_url;
+pub mod coverage;
 pub mod db;
+mod diagnostics;
+pub mod eligibility;
 pub mod exams;
+mod failures;
+pub mod filters;
+pub mod fingerprint;
+mod grades;
+pub mod guides;
+pub mod mock_server;
+pub mod planner;
+pub mod profile;
+mod statistics;
+pub mod trends;
+mod unrecognized;
 pub mod utils;
+pub mod what_if;
+pub mod workload;
 
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
 
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Bits of the error pages IIS/ASP serves with a 200 when something breaks server side
+const ERROR_PAGE_MARKERS: &[&str] = &[
+    "Microsoft OLE DB Provider",
+    "Microsoft VBScript",
+    "Microsoft JET Database Engine",
+    "ADODB.",
+    "Active Server Pages",
+];
+
 struct MyScraper {
     letter_link_selector: Selector,
     course_link_selector: Selector,
     main_headers_selector: Selector,
     course_name_selector: Selector,
     institution_name_selector: Selector,
+    filter: CrawlFilter,
+    /// See `SiteConfig::guide_year`
+    guide_year: Option<u16>,
+    /// Every course queued (or pooled for sampling) so far, and where it was first found
+    sources: HashMap<CourseUrl, EntrySource>,
+    coverage: SharedCoverage,
+    layout: SharedLayoutFingerprint,
+    /// Courses visited (or about to be), counted against `filter.max_courses`
+    queued_courses: usize,
+    /// Letter pages still being crawled, when sampling
+    pending_letters: usize,
+    /// Course links found so far, when sampling
+    sample_pool: Vec<Url>,
 }
 
-impl Default for MyScraper {
-    fn default() -> Self {
+impl MyScraper {
+    fn new(
+        site: &SiteConfig,
+        filter: CrawlFilter,
+        coverage: SharedCoverage,
+        layout: SharedLayoutFingerprint,
+    ) -> Self {
         Self {
-            letter_link_selector: Selector::parse(
-                "a[href*=\"indcurso.asp\"][href*=\"?\"][href*=\"letra=\"]",
-            )
+            letter_link_selector: Selector::parse(&format!(
+                "a[href*=\"{}\"][href*=\"?\"][href*=\"letra=\"]",
+                site.index_page()
+            ))
             .unwrap(),
-            course_link_selector: Selector::parse(
-                "a[href*=\"detcursopi.asp\"][href*=\"?\"][href*=\"codc=\"][href*=\"code=\"]",
-            )
+            course_link_selector: Selector::parse(&format!(
+                "a[href*=\"{}\"][href*=\"?\"][href*=\"codc=\"][href*=\"code=\"]",
+                site.course_page()
+            ))
             .unwrap(),
             main_headers_selector: Selector::parse("#caixa-orange > div.inside2 > h2").unwrap(),
             course_name_selector: Selector::parse("#caixa-orange > div.cab1").unwrap(),
             institution_name_selector: Selector::parse("#caixa-orange > div.cab2").unwrap(),
+            filter,
+            guide_year: site.guide_year,
+            sources: HashMap::new(),
+            coverage,
+            layout,
+            queued_courses: 0,
+            pending_letters: 0,
+            sample_pool: Vec::new(),
+        }
+    }
+
+    /// Remembers where a course was found. `false` if it was already found somewhere else
+    fn add_source(&mut self, course: CourseUrl, source: EntrySource) -> bool {
+        match self.sources.get(&course) {
+            Some(first_source) => {
+                info!(
+                    "Skipping {} from {}: already found in {}",
+                    course, source, first_source
+                );
+                false
+            }
+            None => {
+                self.sources.insert(course, source);
+                true
+            }
         }
     }
+
+    fn queue_course(&mut self, url: Url, source: EntrySource, crawler: &mut Crawler<Self>) {
+        let course = match CourseUrl::try_from(&url) {
+            Ok(course) => course,
+            Err(err) => {
+                warn!("{}", err);
+                return;
+            }
+        };
+        if !self.filter.allows_institution(course.institution_code()) {
+            return;
+        }
+
+        if self.filter.sample.is_none() {
+            if let Some(max_courses) = self.filter.max_courses {
+                if self.queued_courses >= max_courses {
+                    return;
+                }
+            }
+        }
+        if !self.add_source(course.clone(), source) {
+            return;
+        }
+
+        if self.filter.sample.is_some() {
+            self.sample_pool.push(url);
+            return;
+        }
+        self.visit_course(url, course, crawler);
+    }
+
+    fn visit_course(&mut self, url: Url, course: CourseUrl, crawler: &mut Crawler<Self>) {
+        self.queued(course);
+        crawler.visit_with_state(url, MyScraperState::ScrapingCourse);
+    }
+
+    fn queued(&mut self, course: CourseUrl) {
+        if let Some(source) = self.sources.get(&course) {
+            self.coverage.lock().unwrap().queued(course, source.clone());
+        }
+        self.queued_courses += 1;
+    }
+
+    /// Once every letter page is in, visits the sample.
+    ///
+    /// Letter pages whose request fails outright never get here, `next_output` draws the sample
+    /// for those once the crawl runs dry
+    fn letter_done(&mut self, crawler: &mut Crawler<Self>) {
+        self.pending_letters = self.pending_letters.saturating_sub(1);
+        if self.pending_letters > 0 {
+            return;
+        }
+        for url in self.draw_sample() {
+            crawler.visit_with_state(url, MyScraperState::ScrapingCourse);
+        }
+    }
+
+    /// Picks the sample out of all the course links found and counts it as queued. Empty when
+    /// not sampling, or once it's been drawn
+    fn draw_sample(&mut self) -> Vec<Url> {
+        let sample = match self.filter.sample {
+            Some(sample) => sample.min(self.filter.max_courses.unwrap_or(usize::MAX)),
+            None => return Vec::new(),
+        };
+        let pool = std::mem::take(&mut self.sample_pool);
+        if pool.is_empty() {
+            return Vec::new();
+        }
+        info!(
+            "Sampling {} out of {} courses",
+            sample.min(pool.len()),
+            pool.len()
+        );
+        let mut urls = Vec::new();
+        for url in pool.choose_multiple(&mut rand::thread_rng(), sample) {
+            // only parseable course urls make it into the pool
+            if let Ok(course) = CourseUrl::try_from(url) {
+                self.queued(course);
+                urls.push(url.clone());
+            }
+        }
+        urls
+    }
 }
 
 /// The state model
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum MyScraperState {
     FindingLetters,
     IteratingCourses,
     ScrapingCourse,
 }
 
+impl MyScraperState {
+    /// The state a url would be visited with
+    fn from_url(site: &SiteConfig, url: &Url) -> Option<Self> {
+        let page = url.path_segments()?.last()?;
+        if page == site.course_page() {
+            Some(MyScraperState::ScrapingCourse)
+        } else if page == site.index_page() {
+            if url.query_pairs().any(|(key, _)| key == "letra") {
+                Some(MyScraperState::IteratingCourses)
+            } else {
+                Some(MyScraperState::FindingLetters)
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for MyScraperState {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FindingLetters" => Ok(MyScraperState::FindingLetters),
+            "IteratingCourses" => Ok(MyScraperState::IteratingCourses),
+            "ScrapingCourse" => Ok(MyScraperState::ScrapingCourse),
+            _ => Err(()),
+        }
+    }
+}
+
+/// What came out of a single page
+#[derive(Debug)]
+enum ScrapeOutcome {
+    Course(Entry),
+    /// A course page for codes DGES doesn't know about. It's served with a 200
+    NotFound {
+        url: Url,
+        course: CourseUrl,
+        source: EntrySource,
+    },
+    /// The server's error page, served with a 200 instead of the course page
+    ErrorPage {
+        url: Url,
+        course: CourseUrl,
+        source: EntrySource,
+        marker: &'static str,
+    },
+    /// An index page whose links were followed
+    Index(Url),
+    Failed(FailedPage),
+}
+
 #[derive(Debug)]
 struct Entry {
+    url: Url,
+    course: CourseUrl,
+    source: EntrySource,
+    guide_year: Option<u16>,
     characteristics: Characteristics,
     exams: Exams,
+    minimum_grades: MinimumGrades,
+    formula: Formula,
+    statistics: Vec<PhaseStatistics>,
+    unrecognized: Vec<UnrecognizedContent>,
+    diagnostics: Vec<ParseDiagnostic>,
 }
 
 impl Entry {
-    fn new(url: CourseUrl) -> Self {
+    fn new(url: Url, course: CourseUrl, source: EntrySource) -> Self {
         Entry {
+            url,
+            course,
+            source,
+            guide_year: None,
             characteristics: Characteristics::default(),
             exams: Exams::default(),
+            minimum_grades: MinimumGrades::default(),
+            formula: Formula::default(),
+            statistics: Vec::new(),
+            unrecognized: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
-}
-
-/* maybe different file */
-pub(crate) struct CourseUrl(Url);
-
-impl From<Url> for CourseUrl {
-    fn from(value: Url) -> Self {
-        CourseUrl(value)
-    }
-}
 
-impl Debug for CourseUrl {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("CourseUrl")
-            .field(&self.0.to_string())
-            .finish()
+    /// Fields every course page is supposed to have
+    fn missing_fields(&self) -> Vec<&'static str> {
+        let characteristics = &self.characteristics;
+        [
+            ("guide year", self.guide_year.is_none()),
+            ("course code", characteristics.course.code.is_none()),
+            ("course name", characteristics.course.name.is_none()),
+            (
+                "institution code",
+                characteristics.institution.code.is_none(),
+            ),
+            (
+                "institution name",
+                characteristics.institution.name.is_none(),
+            ),
+            ("degree", characteristics.degree.is_none()),
+            ("CNAEF area", characteristics.cnaef_area.code.is_none()),
+            ("duration", characteristics.duration.ammount.is_none()),
+            ("ECTS", characteristics.ects.is_none()),
+            ("education type", characteristics.education_type.is_none()),
+            ("contest", characteristics.contest.is_none()),
+        ]
+        .into_iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(field, _)| field)
+        .collect()
     }
 }
-/* end file */
 
 impl Scraper for MyScraper {
-    type Output = Entry;
+    type Output = ScrapeOutcome;
 
     type State = MyScraperState;
 
@@ -116,11 +383,28 @@ impl Scraper for MyScraper {
         mut response: Response<Self::State>,
         crawler: &mut Crawler<Self>,
     ) -> Result<Option<Self::Output>> {
+        if !response.response_status.is_success() {
+            if let Some(MyScraperState::IteratingCourses) = response.state {
+                self.letter_done(crawler);
+            }
+            return Ok(Some(ScrapeOutcome::Failed(FailedPage {
+                url: response.request_url,
+                state: response.state,
+                kind: FailureKind::Status,
+                attempts: 1,
+                error: format!("HTTP {}", response.response_status),
+            })));
+        }
+
         let html = response.html();
         match response.state {
             Some(state) => {
                 match state {
                     MyScraperState::FindingLetters => {
+                        let mut fingerprint = PageFingerprint::default();
+                        fingerprint.selector("letter_link", &html, &self.letter_link_selector);
+                        self.layout.lock().unwrap().record("index", fingerprint);
+
                         let letters = html.select(&self.letter_link_selector);
                         for node in letters {
                             let url = &mut response.response_url;
@@ -133,11 +417,34 @@ impl Scraper for MyScraper {
                             }
                             url.set_query(href.last());
 
+                            let letter = url
+                                .query_pairs()
+                                .find(|(key, _)| key == "letra")
+                                .map(|(_, value)| value.into_owned())
+                                .unwrap_or_default();
+                            if !self.filter.allows_letter(&letter) {
+                                continue;
+                            }
+
+                            self.coverage.lock().unwrap().letter_found(&letter);
+                            self.pending_letters += 1;
                             crawler.visit_with_state(url.clone(), MyScraperState::IteratingCourses);
                         }
+                        return Ok(Some(ScrapeOutcome::Index(response.request_url)));
                     }
 
                     MyScraperState::IteratingCourses => {
+                        let letter = response
+                            .request_url
+                            .query_pairs()
+                            .find(|(key, _)| key == "letra")
+                            .map(|(_, value)| value.into_owned())
+                            .unwrap_or_default();
+                        let mut fingerprint = PageFingerprint::default();
+                        fingerprint.selector("course_link", &html, &self.course_link_selector);
+                        self.layout.lock().unwrap().record("letter", fingerprint);
+
+                        let mut course_urls = Vec::new();
                         let courses = html.select(&self.course_link_selector);
                         for node in courses {
                             let url = &mut response.response_url;
@@ -149,47 +456,137 @@ impl Scraper for MyScraper {
                                 path_segments.push(href.next().unwrap());
                             }
                             url.set_query(href.last());
-                            crawler.visit_with_state(url.clone(), MyScraperState::ScrapingCourse);
+                            course_urls.push(url.clone());
                         }
+                        self.coverage
+                            .lock()
+                            .unwrap()
+                            .letter_scraped(&letter, course_urls.len());
+                        for url in course_urls {
+                            self.queue_course(
+                                url,
+                                EntrySource::LetterIndex(letter.clone()),
+                                crawler,
+                            );
+                        }
+                        self.letter_done(crawler);
+                        return Ok(Some(ScrapeOutcome::Index(response.request_url)));
                     }
                     MyScraperState::ScrapingCourse => {
-                        let mut entry = Entry::new(response.request_url.clone().into());
+                        let course = CourseUrl::try_from(&response.request_url)?;
+                        let source = match self.sources.get(&course) {
+                            Some(source) => source.clone(),
+                            None => bail!("{} was visited without being queued", course),
+                        };
+                        let page_text: String = html.root_element().text().collect();
+                        if let Some(marker) = ERROR_PAGE_MARKERS
+                            .iter()
+                            .copied()
+                            .find(|marker| page_text.contains(marker))
+                        {
+                            return Ok(Some(ScrapeOutcome::ErrorPage {
+                                url: response.request_url,
+                                course,
+                                source,
+                                marker,
+                            }));
+                        }
+
+                        let mut entry = Entry::new(response.request_url.clone(), course, source);
                         let url: String = response.request_url.to_string();
                         dbg!(url);
 
+                        let mut fingerprint = PageFingerprint::default();
+                        fingerprint.selector("main_headers", &html, &self.main_headers_selector);
+                        fingerprint.selector("course_name", &html, &self.course_name_selector);
+                        fingerprint.selector(
+                            "institution_name",
+                            &html,
+                            &self.institution_name_selector,
+                        );
+
+                        let mut has_characteristics = false;
                         for header in html.select(&self.main_headers_selector) {
+                            fingerprint.header(&header.inner_html());
                             match header.inner_html().as_str() {
-                            "Endereço e Contactos da Instituição" => {
-                                let mut iter = header.next_siblings();
-                                entry.characteristics.set_institution_meh(institution_contacts_section(&mut iter));
-                            }
-                            "Características do par Instituição/Curso" => {
-                                let mut iter = header.next_siblings();
-                                //TODO
-                                entry.characteristics.set_most_of_them(characteristics_section(&mut iter));
-                            }
-                            "Provas de Ingresso" => {
-                                let mut iter = header.next_siblings();
-                                entry.exams = exams_section(&mut iter);
-                            }
-                            "Dados Estatísticos de Candidaturas Anteriores" => {
-                                statistics_section(header);
-                            }
-                            "Outras Informações" => {
-                                information_section(header);
-                            }
-                            // useless but known headers
-                            "Guia das Provas de Ingresso de 2022 - Detalhe de Curso<br>&nbsp;"
-                            | "some more headers"
-                            | "some more headerss"
-                            | "some more headersss" => {}
-
-                            // unknown headers
-                            text => {
-                                //TODO: This should store unkown headers somewhere
-                                info!("UNKNOWN HEADER: {}", text);
+                                "Endereço e Contactos da Instituição" => {
+                                    let mut iter = header.next_siblings();
+                                    entry.characteristics.set_institution_meh(
+                                        institution_contacts_section(&mut iter),
+                                    );
+                                }
+                                "Características do par Instituição/Curso" => {
+                                    has_characteristics = true;
+                                    fingerprint.fields_after(header);
+                                    let mut iter = header.next_siblings();
+                                    //TODO
+                                    entry.characteristics.set_most_of_them(
+                                        characteristics_section(
+                                            &mut iter,
+                                            &mut entry.unrecognized,
+                                            &mut entry.diagnostics,
+                                        ),
+                                    );
+                                }
+                                "Provas de Ingresso" => {
+                                    let mut iter = header.next_siblings();
+                                    entry.exams = exams_section(&mut iter, &mut entry.diagnostics);
+                                }
+                                "Classificações Mínimas" => {
+                                    fingerprint.fields_after(header);
+                                    let mut iter = header.next_siblings();
+                                    entry.minimum_grades = minimum_grades_section(
+                                        &mut iter,
+                                        &mut entry.unrecognized,
+                                        &mut entry.diagnostics,
+                                    );
+                                }
+                                "Fórmula de Cálculo" => {
+                                    fingerprint.fields_after(header);
+                                    let mut iter = header.next_siblings();
+                                    entry.formula = formula_section(
+                                        &mut iter,
+                                        &mut entry.unrecognized,
+                                        &mut entry.diagnostics,
+                                    );
+                                }
+                                "Dados Estatísticos de Candidaturas Anteriores" => {
+                                    entry.statistics = statistics_section(
+                                        header,
+                                        &mut entry.unrecognized,
+                                        &mut entry.diagnostics,
+                                    );
+                                }
+                                "Outras Informações" => {
+                                    information_section(header);
+                                }
+                                text if guide_year(text).is_some() => {
+                                    entry.guide_year = guide_year(text);
+                                }
+                                // useless but known headers
+                                "some more headers"
+                                | "some more headerss"
+                                | "some more headersss" => {}
+
+                                // unknown headers
+                                text => {
+                                    info!("UNKNOWN HEADER: {}", text);
+                                    entry.unrecognized.push(UnrecognizedContent::header(header));
+                                }
                             }
                         }
+
+                        match (entry.guide_year, self.guide_year) {
+                            (Some(page), Some(expected)) if page != expected => {
+                                entry.diagnostics.push(ParseDiagnostic::new(
+                                    "guide",
+                                    "year",
+                                    &page.to_string(),
+                                    format!("expected the {} guide", expected),
+                                ));
+                            }
+                            (None, Some(expected)) => entry.guide_year = Some(expected),
+                            _ => {}
                         }
 
                         entry.characteristics.course.name =
@@ -216,7 +613,17 @@ impl Scraper for MyScraper {
                                 None => None,
                             };
 
-                        return Ok(Some(entry));
+                        // unknown codes get the same template, with nothing in it
+                        if !has_characteristics && entry.characteristics.course.name.is_none() {
+                            return Ok(Some(ScrapeOutcome::NotFound {
+                                url: entry.url,
+                                course: entry.course,
+                                source: entry.source,
+                            }));
+                        }
+
+                        self.layout.lock().unwrap().record("course", fingerprint);
+                        return Ok(Some(ScrapeOutcome::Course(entry)));
                     }
                 }
             }
@@ -299,177 +706,895 @@ fn institution_contacts_section<'a>(
     }
     institution
 }
-fn statistics_section(element: ElementRef) {}
 fn information_section(element: ElementRef) {}
 
-pub async fn all_courses() -> MyCollector {
-    tracing_subscriber::fmt::init();
+async fn crawler_config(
+    site: &SiteConfig,
+    client: ClientConfig,
+    failed_pages: &FailedPages,
+) -> CrawlerConfig {
+    let on_give_up = {
+        let site = site.clone();
+        let failed_pages = failed_pages.clone();
+        move |gave_up: GaveUp| {
+            let failed_page = FailedPage {
+                state: MyScraperState::from_url(&site, &gave_up.url),
+                url: gave_up.url,
+                kind: match gave_up.status {
+                    Some(_) => FailureKind::Status,
+                    None => FailureKind::Fetch,
+                },
+                attempts: gave_up.attempts,
+                error: gave_up.error,
+            };
+            failed_pages.lock().unwrap().push(failed_page);
+        }
+    };
+
+    let reqwest_client = reqwest::ClientBuilder::new()
+        .user_agent(USER_AGENT)
+        .build()
+        .unwrap();
+    let replaying = matches!(client.cassette, Some((CassetteMode::Replay, _)));
+
+    let mut client_builder = ClientBuilder::new(reqwest_client.clone())
+        .with(HtmlCharsetWindows1252)
+        .with(RetryMiddleware::new(
+            client.retry.policy(),
+            client.retry.retryable_statuses.iter().copied(),
+            on_give_up,
+        ));
+    // no point in being polite to a cassette
+    if !replaying {
+        let crawl_delay = if client.politeness.respect_crawl_delay {
+            robots_crawl_delay(site, reqwest_client).await
+        } else {
+            None
+        };
+        client_builder = client_builder.with(ThrottleMiddleware::new(
+            client.politeness.throttle_config(crawl_delay),
+        ));
+    }
+    if let Some((mode, directory)) = client.cassette {
+        client_builder = client_builder.with(CassetteMiddleware::new(mode, directory));
+    }
 
-    let config = CrawlerConfig::default()
-        .respect_robots_txt()
-        .allow_domain_with_delay(
-            "dges.gov.pt",
-            RequestDelay::Fixed(std::time::Duration::from_secs(10)),
+    // the throttle middleware takes care of spacing requests out
+    site.allowed_domains()
+        .into_iter()
+        .fold(
+            CrawlerConfig::default().respect_robots_txt(),
+            |config, domain| config.allow_domain(domain),
         )
-        //.scrape_non_success_response()
-        .set_client(
-            ClientBuilder::new(reqwest::ClientBuilder::new().build().unwrap())
-                .with(HtmlCharsetWindows1252)
-                .build(),
-        );
+        .scrape_non_success_response()
+        .set_client(client_builder.build())
+}
+
+/// `Crawl-delay` from the site's robots.txt, if there's one
+async fn robots_crawl_delay(site: &SiteConfig, client: reqwest::Client) -> Option<Duration> {
+    let url = site.base_url.join("/robots.txt").ok()?;
+    let response = ClientBuilder::new(client)
+        .with(HtmlCharsetWindows1252)
+        .build()
+        .get(url)
+        .send()
+        .await
+        .ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let crawl_delay = crawl_delay(&response.text().await.ok()?, USER_AGENT);
+    if let Some(crawl_delay) = crawl_delay {
+        info!("robots.txt asks for a Crawl-delay of {:?}", crawl_delay);
+    }
+    crawl_delay
+}
+
+pub async fn all_courses(
+    site: SiteConfig,
+    client: ClientConfig,
+    filter: CrawlFilter,
+) -> MyCollector {
+    let failed_pages = FailedPages::default();
+    let config = crawler_config(&site, client, &failed_pages).await;
 
-    let mut collector = Collector::new(MyScraper::default(), config);
-    collector.crawler_mut().visit_with_state(
-        "https://dges.gov.pt/guias/indcurso.asp",
-        MyScraperState::FindingLetters,
+    let coverage = SharedCoverage::default();
+    let layout = SharedLayoutFingerprint::default();
+
+    let mut collector = Collector::new(
+        MyScraper::new(&site, filter.clone(), coverage.clone(), layout.clone()),
+        config,
     );
+    collector
+        .crawler_mut()
+        .visit_with_state(site.index_url(), MyScraperState::FindingLetters);
 
-    MyCollector(collector)
+    MyCollector {
+        collector,
+        failed_pages,
+        partial: filter.is_partial(),
+        filter,
+        coverage,
+        layout,
+    }
 }
 
-pub(super) async fn select_courses(courses: impl Iterator<Item = Record>) -> MyCollector {
-    tracing_subscriber::fmt::init();
+pub(super) async fn select_courses(
+    site: SiteConfig,
+    client: ClientConfig,
+    filter: CrawlFilter,
+    courses: impl Iterator<Item = Record>,
+) -> MyCollector {
+    let failed_pages = FailedPages::default();
+    let config = crawler_config(&site, client, &failed_pages).await;
 
-    let config = CrawlerConfig::default()
-        .respect_robots_txt()
-        .allow_domain_with_delay(
-            "dges.gov.pt",
-            RequestDelay::Fixed(std::time::Duration::from_secs(10)),
-        )
-        //.scrape_non_success_response()
-        .set_client(
-            ClientBuilder::new(reqwest::ClientBuilder::new().build().unwrap())
-                .with(HtmlCharsetWindows1252)
-                .build(),
-        );
+    let coverage = SharedCoverage::default();
+    let layout = SharedLayoutFingerprint::default();
+    let mut scraper = MyScraper::new(&site, filter.clone(), coverage.clone(), layout.clone());
 
-    let mut collector = Collector::new(MyScraper::default(), config);
+    // rows are numbered before filtering, so they still point at the right line of the file
+    let mut courses: Vec<(usize, Record)> = courses
+        .enumerate()
+        .map(|(i, record)| (i + 1, record))
+        .filter(|(_, record)| filter.allows_institution(&record.institution_code))
+        .filter(|(row, record)| {
+            scraper.add_source(
+                CourseUrl::new(&record.institution_code, &record.course_code),
+                EntrySource::Csv { row: *row },
+            )
+        })
+        .collect();
+    if let Some(sample) = filter.sample {
+        courses = courses
+            .choose_multiple(&mut rand::thread_rng(), sample)
+            .cloned()
+            .collect();
+    }
+    if let Some(max_courses) = filter.max_courses {
+        courses.truncate(max_courses);
+    }
 
-    for Record {
-        course_code,
-        institution_code,
-    } in courses
+    let mut collector = Collector::new(scraper, config);
+    for (
+        row,
+        Record {
+            course_code,
+            institution_code,
+        },
+    ) in courses
     {
-        let course_code: String = course_code.into();
-        let institution_code: String = institution_code.into();
+        coverage.lock().unwrap().queued(
+            CourseUrl::new(&institution_code, &course_code),
+            EntrySource::Csv { row },
+        );
         collector.crawler_mut().visit_with_state(
-            format!(
-                "https://dges.gov.pt/guias/detcursopi.asp?codc={}&code={}",
-                course_code, institution_code
-            ),
+            site.course_url(&institution_code, &course_code),
             MyScraperState::ScrapingCourse,
         );
     }
 
-    MyCollector(collector)
+    MyCollector {
+        collector,
+        failed_pages,
+        filter,
+        coverage,
+        layout,
+        // a list of courses, not the whole site
+        partial: true,
+    }
+}
+
+pub async fn retry_failed(
+    site: SiteConfig,
+    client: ClientConfig,
+    filter: CrawlFilter,
+    conn: &mut SqliteConnection,
+) -> Result<MyCollector> {
+    let failed_pages = FailedPages::default();
+    let config = crawler_config(&site, client, &failed_pages).await;
+
+    let coverage = SharedCoverage::default();
+    let layout = SharedLayoutFingerprint::default();
+    let mut scraper = MyScraper::new(&site, filter.clone(), coverage.clone(), layout.clone());
+    let mut visits = Vec::new();
+
+    for failed_page in get_failed_pages(conn)? {
+        let url = match Url::parse(&failed_page.url) {
+            Ok(url) => url,
+            Err(err) => {
+                warn!("Skipping bad url {}: {}", failed_page.url, err);
+                continue;
+            }
+        };
+        let state = failed_page
+            .state
+            .and_then(|state| state.parse().ok())
+            .or_else(|| MyScraperState::from_url(&site, &url));
+        match state {
+            Some(MyScraperState::ScrapingCourse) => match CourseUrl::try_from(&url) {
+                Ok(course) => {
+                    if scraper.add_source(course.clone(), EntrySource::RetryFailed) {
+                        coverage
+                            .lock()
+                            .unwrap()
+                            .queued(course, EntrySource::RetryFailed);
+                        visits.push((url, MyScraperState::ScrapingCourse));
+                    }
+                }
+                Err(err) => warn!("Skipping {}", err),
+            },
+            Some(state) => visits.push((url, state)),
+            None => warn!("Skipping {}: don't know how to scrape it", url),
+        }
+    }
+
+    let mut collector = Collector::new(scraper, config);
+    for (url, state) in visits {
+        collector.crawler_mut().visit_with_state(url, state);
+    }
+
+    Ok(MyCollector {
+        collector,
+        failed_pages,
+        filter,
+        coverage,
+        layout,
+        partial: true,
+    })
 }
 
-pub struct MyCollector(Collector<MyScraper>);
+pub struct MyCollector {
+    collector: Collector<MyScraper>,
+    failed_pages: FailedPages,
+    filter: CrawlFilter,
+    coverage: SharedCoverage,
+    layout: SharedLayoutFingerprint,
+    /// Only meant to visit part of the site
+    partial: bool,
+}
+
+impl MyCollector {
+    /// The structure of the pages crawled so far
+    pub fn layout(&self) -> LayoutFingerprint {
+        self.layout.lock().unwrap().clone()
+    }
+
+    /// Only meant to visit part of the site
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
 
-pub async fn handle_results(collector: &mut MyCollector) {
-    let collector = &mut collector.0;
-    let mut conn = establish_connection();
+/// Saves everything the crawl comes up with, and reports what it covered
+pub async fn handle_results(collector: &mut MyCollector, conn: &mut SqliteConnection) -> Coverage {
+    let failed_pages = collector.failed_pages.clone();
+    let filter = collector.filter.clone();
+    let coverage = collector.coverage.clone();
+    let partial = collector.partial;
+    let collector = &mut collector.collector;
 
     //TODO: HANDLE THIS ERROR
     conn.run_pending_migrations(MIGRATIONS)
         .expect("Please migration god, be good!");
 
-    while let Some(output) = collector.next().await {
-        if let Ok(course) = output {
-            if let (
-                Some(code),
-                Some(name),
-                Some(address),
-                Some(phone_numbers),
-                Some(email_addresses),
-            ) = (
-                course.characteristics.institution.code,
-                course.characteristics.institution.name,
-                course.characteristics.institution.address,
-                course.characteristics.institution.phone_numbers,
-                course.characteristics.institution.email_addresses,
-            ) {
-                let code: String = code.into();
-                let name: String = name.into();
-                let address: Vec<String> = address.into();
-                if let Ok(institution) = create_institution(
-                    &mut conn,
+    let run = match create_run(conn, &chrono::Utc::now().to_rfc3339()) {
+        Ok(run) => Some(run.id),
+        Err(err) => {
+            warn!(
+                "Couldn't record this run, unrecognized content won't be saved: {}",
+                err
+            );
+            None
+        }
+    };
+
+    let mut guide_years = BTreeSet::new();
+    while let Some(output) = next_output(collector).await {
+        match output {
+            Ok(ScrapeOutcome::Course(mut course)) => {
+                let url = course.url.clone();
+                guide_years.extend(course.guide_year);
+                let cnaef_area_code = course
+                    .characteristics
+                    .cnaef_area
+                    .code
+                    .as_ref()
+                    .map(|code| code.to_string());
+                if !filter.allows_cnaef_area(cnaef_area_code.as_deref()) {
+                    info!("Skipping {}: CNAEF area filtered out", url);
+                    coverage.lock().unwrap().filtered_out(&course.course);
+                    continue;
+                }
+                let (course_key, source) = (course.course.clone(), course.source.clone());
+                if let Some(diagnostic) = incomplete_exam(&course.exams) {
+                    course.diagnostics.push(diagnostic);
+                }
+                let anomalies = check_workload(
+                    &course.characteristics.duration,
+                    course.characteristics.ects,
+                );
+                if let Some(run) = run {
+                    save_unrecognized_content(conn, run, &course);
+                    save_parse_diagnostics(conn, run, &course);
+                    save_anomalies(conn, run, &course, &anomalies);
+                }
+                match course.guide_year {
+                    Some(guide_year) => {
+                        save_admission_requirements(conn, guide_year, &course);
+                        save_workload(conn, guide_year, &course);
+                    }
+                    None => warn!("{}: no guide year, only its statistics are saved", url),
+                }
+                save_statistics(conn, &course);
+                coverage
+                    .lock()
+                    .unwrap()
+                    .diagnosed(course_key.clone(), &course.diagnostics);
+                coverage
+                    .lock()
+                    .unwrap()
+                    .anomalies(course_key.clone(), &anomalies);
+                coverage
+                    .lock()
+                    .unwrap()
+                    .scraped(course_key.clone(), course.missing_fields());
+                match save_entry(conn, course) {
+                    Ok(()) => {
+                        coverage.lock().unwrap().persisted(course_key);
+                        delete_failed_page(conn, url.as_str())
+                    }
+                    Err(err) => {
+                        warn!("Couldn't save {} ({}): {}", course_key, source, err);
+                        coverage.lock().unwrap().failed(course_key, err.to_string());
+                        save_failed_page(
+                            conn,
+                            &FailedPage {
+                                url,
+                                state: Some(MyScraperState::ScrapingCourse),
+                                kind: FailureKind::Parse,
+                                attempts: 1,
+                                error: err.to_string(),
+                            },
+                        )
+                    }
+                }
+            }
+            Ok(ScrapeOutcome::NotFound {
+                url,
+                course,
+                source,
+            }) => {
+                warn!("{}: course {} not found", source, course);
+                coverage.lock().unwrap().not_found(course);
+                // retrying won't make it exist
+                delete_failed_page(conn, url.as_str());
+            }
+            Ok(ScrapeOutcome::ErrorPage {
+                url,
+                course,
+                source,
+                marker,
+            }) => {
+                warn!("{}: got an error page for {} ({})", source, course, marker);
+                let failed_page = FailedPage {
+                    url,
+                    state: Some(MyScraperState::ScrapingCourse),
+                    kind: FailureKind::ErrorPage,
+                    attempts: 1,
+                    error: format!("error page ({})", marker),
+                };
+                record_failure(&coverage, &failed_page);
+                save_failed_page(conn, &failed_page);
+            }
+            Ok(ScrapeOutcome::Index(url)) => delete_failed_page(conn, url.as_str()),
+            Ok(ScrapeOutcome::Failed(failed_page)) => {
+                record_failure(&coverage, &failed_page);
+                save_failed_page(conn, &failed_page)
+            }
+            // the url isn't known here, the retry middleware records these
+            Err(err) => info!("{}", err),
+        }
+    }
+
+    // after the pages above, so the attempt count from the retry middleware wins
+    for failed_page in failed_pages.lock().unwrap().drain(..) {
+        warn!(
+            "Gave up on {} ({:?}) after {} attempts: {}",
+            failed_page.url, failed_page.state, failed_page.attempts, failed_page.error
+        );
+        record_failure(&coverage, &failed_page);
+        save_failed_page(conn, &failed_page);
+    }
+
+    let coverage = std::mem::take(&mut *coverage.lock().unwrap());
+    info!("Coverage: {}", coverage.summary());
+    if let Some(run) = run {
+        // a run that saw pages from more than one guide didn't see all of any of them
+        let guide_year = match guide_years.len() {
+            1 => guide_years.into_iter().next().map(i32::from),
+            _ => None,
+        };
+        let partial = partial || guide_year.is_none() || !coverage.saw_every_course();
+        update_run(conn, run, guide_year, partial);
+    }
+    if !coverage.is_complete() {
+        warn!("Some courses went missing, see the coverage report");
+    }
+    coverage
+}
+
+/// The next thing the crawl comes up with.
+///
+/// A sample is drawn when the last letter page is scraped, but letter pages whose request fails
+/// outright are never scraped. If any did, the sample is drawn here, once nothing else is left
+async fn next_output(
+    collector: &mut Collector<MyScraper>,
+) -> Option<<Collector<MyScraper> as Stream>::Item> {
+    if let Some(output) = collector.next().await {
+        return Some(output);
+    }
+    let sample = collector.scraper_mut().draw_sample();
+    if sample.is_empty() {
+        return None;
+    }
+    for url in sample {
+        collector
+            .crawler_mut()
+            .visit_with_state(url, MyScraperState::ScrapingCourse);
+    }
+    collector.next().await
+}
+
+/// Lets the coverage report say why a course never came back
+fn record_failure(coverage: &SharedCoverage, failed_page: &FailedPage) {
+    if let Ok(course) = CourseUrl::try_from(&failed_page.url) {
+        coverage
+            .lock()
+            .unwrap()
+            .failed(course, failed_page.error.clone());
+    }
+}
+
+fn save_failed_page(conn: &mut SqliteConnection, failed_page: &FailedPage) {
+    let state = failed_page
+        .state
+        .as_ref()
+        .map(|state| format!("{:?}", state));
+    create_failed_page(
+        conn,
+        failed_page.url.as_str(),
+        state.as_deref(),
+        failed_page.kind.as_str(),
+        failed_page.attempts as i32,
+        &failed_page.error,
+    );
+}
+
+fn save_unrecognized_content(conn: &mut SqliteConnection, run: i32, course: &Entry) {
+    for content in course.unrecognized.iter() {
+        create_unrecognized_content(
+            conn,
+            run,
+            course.course.institution_code(),
+            course.course.course_code(),
+            content.kind.as_str(),
+            &content.name,
+            content.raw_value.as_deref(),
+            &content.snippet,
+        );
+    }
+}
+
+fn save_parse_diagnostics(conn: &mut SqliteConnection, run: i32, course: &Entry) {
+    for diagnostic in course.diagnostics.iter() {
+        create_parse_diagnostic(
+            conn,
+            run,
+            course.course.institution_code(),
+            course.course.course_code(),
+            diagnostic.section,
+            &diagnostic.field,
+            &diagnostic.raw_value,
+            &diagnostic.error,
+        );
+    }
+}
+
+fn save_anomalies(conn: &mut SqliteConnection, run: i32, course: &Entry, anomalies: &[Anomaly]) {
+    for anomaly in anomalies {
+        create_anomaly(
+            conn,
+            run,
+            course.course.institution_code(),
+            course.course.course_code(),
+            anomaly.kind(),
+            &anomaly.to_string(),
+        );
+    }
+}
+
+fn save_workload(conn: &mut SqliteConnection, guide_year: u16, course: &Entry) {
+    let workload = Workload::new(
+        &course.characteristics.duration,
+        course.characteristics.ects,
+    );
+    create_workload(
+        conn,
+        i32::from(guide_year),
+        course.course.institution_code(),
+        course.course.course_code(),
+        workload.months.map(i32::from),
+        workload.ects.map(i32::from),
+        workload.ects_per_year(),
+    );
+}
+
+/// The exams, minimum grades and formula a course applies to candidates, replacing what an earlier
+/// crawl saved
+fn save_admission_requirements(conn: &mut SqliteConnection, guide_year: u16, course: &Entry) {
+    let guide_year = i32::from(guide_year);
+    let (institution, course_code) = (
+        course.course.institution_code(),
+        course.course.course_code(),
+    );
+
+    let name = course.characteristics.course.name.clone().map(String::from);
+    create_guide_course(conn, guide_year, institution, course_code, name.as_deref());
+
+    delete_exam_requirements(conn, guide_year, institution, course_code);
+    // part of the requirement would let in candidates the course doesn't, `incomplete_exam`
+    // leaves a diagnostic instead
+    if incomplete_exam(&course.exams).is_none() {
+        save_exam_requirements(conn, guide_year, institution, course_code, &course.exams);
+    }
+
+    let minimum_grades = &course.minimum_grades;
+    create_minimum_grades(
+        conn,
+        guide_year,
+        institution,
+        course_code,
+        minimum_grades.application.map(Grade::points),
+        minimum_grades.exams.map(Grade::points),
+    );
+
+    let formula = &course.formula;
+    create_formula(
+        conn,
+        guide_year,
+        institution,
+        course_code,
+        formula.secondary_average.map(i32::from),
+        formula.exams.map(i32::from),
+    );
+}
+
+fn save_exam_requirements(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    institution: &str,
+    course_code: &str,
+    exams: &Exams,
+) {
+    let mandatory = exams
+        .mandatory
+        .iter()
+        .flat_map(|mandatory| mandatory.iter())
+        .map(|exam| vec![vec![exam]]);
+    let choices = exams
+        .optional
+        .iter()
+        .flat_map(|optional| optional.iter())
+        .map(|choice| {
+            choice
+                .iter()
+                .map(|group| group.iter().collect::<Vec<_>>())
+                .collect::<Vec<_>>()
+        });
+    for (choice, options) in mandatory.chain(choices).enumerate() {
+        for (option, exams) in options.into_iter().enumerate() {
+            for exam in exams {
+                let (code, name) = match (&exam.code, &exam.name) {
+                    (Some(code), Some(name)) => (code.to_string(), String::from(name.clone())),
+                    // checked by the caller
+                    _ => continue,
+                };
+                // the exams table is what the foreign key points at
+                create_exam(conn, &code, &name).ok();
+                create_exam_requirement(
+                    conn,
+                    guide_year,
+                    institution,
+                    course_code,
+                    choice as i32,
+                    option as i32,
                     &code,
-                    &name,
-                    address.iter(),
-                    phone_numbers.into_iter(),
-                    email_addresses.into_iter(),
-                ) {
-                    if let Some(ects) = course.characteristics.ects {
-                        let ects: u16 = ects.into();
-                        let main = create_main(&mut conn, ects as i32, institution.rowid);
-
-                        if let Ok(main) = main {
-                            if let Some(name) = course.characteristics.duration.unit {
-                                let name: String = name.into();
-                                let duration_unit = create_duration_unit(&mut conn, &name);
-                                if let Some(ammount) = course.characteristics.duration.ammount {
-                                    if let Ok(duration_unit) = duration_unit {
-                                        let ammount: u8 = ammount.into();
-                                        create_duration(
-                                            &mut conn,
-                                            main.rowid,
-                                            duration_unit.rowid,
-                                            ammount as i32,
-                                        );
-                                    }
-                                }
-                            }
+                );
+            }
+        }
+    }
+}
 
-                            if let Some(code) = course.characteristics.cnaef_area.code {
-                                if let Some(name) = course.characteristics.cnaef_area.name {
-                                    let code: String = code.into();
-                                    let name: String = name.into();
-                                    create_cnaef_area(&mut conn, &code, &name);
-                                }
-                            }
+/// A diagnostic for the first exam without a code or name, if there's one
+fn incomplete_exam(exams: &Exams) -> Option<ParseDiagnostic> {
+    let mandatory = exams
+        .mandatory
+        .iter()
+        .flat_map(|mandatory| mandatory.iter());
+    let optional = exams
+        .optional
+        .iter()
+        .flat_map(|optional| optional.iter())
+        .flat_map(|choice| choice.iter())
+        .flat_map(|group| group.iter());
+    let exam = mandatory
+        .chain(optional)
+        .find(|exam| exam.code.is_none() || exam.name.is_none())?;
+    let (raw_value, error) = match (&exam.code, &exam.name) {
+        (Some(code), _) => (
+            code.to_string(),
+            "exam without a name, requirements not saved",
+        ),
+        (None, name) => (
+            name.clone().map(String::from).unwrap_or_default(),
+            "exam without a code, requirements not saved",
+        ),
+    };
+    Some(ParseDiagnostic::new("exams", "exam", &raw_value, error))
+}
 
-                            if let Some(exams) = course.exams.optional {
-                                for exams in exams {
-                                    for exam_group in exams {
-                                        for exam in exam_group {
-                                            if let Some(code) = exam.code {
-                                                if let Some(name) = exam.name {
-                                                    let code: String = code.into();
-                                                    let name: String = name.into();
-
-                                                    create_exam(&mut conn, &code, &name);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+fn save_statistics(conn: &mut SqliteConnection, course: &Entry) {
+    for phase in course.statistics.iter() {
+        create_statistics(
+            conn,
+            course.course.institution_code(),
+            course.course.course_code(),
+            phase.year as i32,
+            phase.phase as i32,
+            phase.vacancies.map(i32::from),
+            phase.applicants.map(i32::from),
+            phase.placed.map(i32::from),
+            phase.last_placed_grade.map(Grade::points),
+        );
+    }
+}
 
-                            if let Some(exams) = course.exams.mandatory {
-                                for exam in exams {
-                                    if let Some(code) = exam.code {
-                                        if let Some(name) = exam.name {
-                                            let code: String = code.into();
-                                            let name: String = name.into();
-                                            if let Ok(exam) = create_exam(&mut conn, &code, &name) {
-                                                create_mandatory_exam(
-                                                    &mut conn, exam.rowid, main.rowid,
-                                                );
-                                            } else {
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+/// How often each unrecognized header and field showed up, most common first
+pub fn unrecognized_content_report(
+    conn: &mut SqliteConnection,
+    run: Option<i32>,
+) -> Result<String> {
+    let mut frequencies: HashMap<(String, String), (usize, Option<String>)> = HashMap::new();
+    for content in get_unrecognized_content(conn, run)? {
+        let (count, example) = frequencies
+            .entry((content.kind, content.name))
+            .or_insert((0, None));
+        *count += 1;
+        if example.is_none() {
+            *example = content.raw_value;
+        }
+    }
+
+    let mut frequencies: Vec<_> = frequencies.into_iter().collect();
+    frequencies.sort_by(|(a_key, (a_count, _)), (b_key, (b_count, _))| {
+        b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+    });
+
+    let mut report = String::new();
+    for ((kind, name), (count, example)) in frequencies {
+        report.push_str(&format!("{:>6}  {:<6}  {}", count, kind, name));
+        if let Some(example) = example {
+            report.push_str(&format!(" (e.g. \"{}\")", example));
+        }
+        report.push('\n');
+    }
+    Ok(report)
+}
+
+fn save_entry(conn: &mut SqliteConnection, course: Entry) -> Result<()> {
+    let guide_year = match course.guide_year {
+        Some(guide_year) => i32::from(guide_year),
+        None => bail!("Missing guide year"),
+    };
+    let (institution, course_code) = (
+        course.course.institution_code(),
+        course.course.course_code(),
+    );
+
+    let (code, name, address, phone_numbers, email_addresses) = match (
+        course.characteristics.institution.code,
+        course.characteristics.institution.name,
+        course.characteristics.institution.address,
+        course.characteristics.institution.phone_numbers,
+        course.characteristics.institution.email_addresses,
+    ) {
+        (Some(code), Some(name), Some(address), Some(phone_numbers), Some(email_addresses)) => {
+            (code, name, address, phone_numbers, email_addresses)
+        }
+        _ => bail!("Missing institution code, name, address, phone numbers or email addresses"),
+    };
+
+    let code: String = code.into();
+    let name: String = name.into();
+    let address: Vec<String> = address.into();
+    create_institution(
+        conn,
+        guide_year,
+        &code,
+        &name,
+        address.iter(),
+        phone_numbers.into_iter(),
+        email_addresses.into_iter(),
+    );
+
+    let ects: u16 = match course.characteristics.ects {
+        Some(ects) => ects.into(),
+        None => bail!("Missing ECTS"),
+    };
+    let name = course.characteristics.course.name.map(String::from);
+    create_course(conn, guide_year, course_code, name.as_deref());
+    create_course_institution(
+        conn,
+        guide_year,
+        institution,
+        course_code,
+        Some(ects as i32),
+    );
+
+    let unit = course.characteristics.duration.unit.map(String::from);
+    if let Some(unit) = &unit {
+        create_duration_unit(conn, unit);
+    }
+    let ammount = course
+        .characteristics
+        .duration
+        .ammount
+        .map(|ammount| u8::from(ammount) as i32);
+    create_duration(
+        conn,
+        guide_year,
+        institution,
+        course_code,
+        unit.as_deref(),
+        ammount,
+    );
+
+    if let Some(code) = course.characteristics.cnaef_area.code {
+        if let Some(name) = course.characteristics.cnaef_area.name {
+            let code: String = code.into();
+            let name: String = name.into();
+            match (Level::of(&code), area_at(&code, Level::BroadGroup)) {
+                (Some(level), Some(broad_group)) => {
+                    create_cnaef_area(conn, &code, &name, level.into(), parent(&code));
+                    create_course_area(
+                        conn,
+                        guide_year,
+                        institution,
+                        course_code,
+                        broad_group,
+                        area_at(&code, Level::NarrowField),
+                        &code,
+                    );
+                }
+                _ => warn!("{}: \"{}\" isn't a CNAEF area code", course.course, code),
+            }
+        }
+    }
+
+    if let Some(exams) = course.exams.optional {
+        for exams in exams {
+            for exam_group in exams {
+                for exam in exam_group {
+                    if let Some(code) = exam.code {
+                        if let Some(name) = exam.name {
+                            let code: String = code.into();
+                            let name: String = name.into();
+
+                            create_exam(conn, &code, &name);
                         }
                     }
                 }
             }
         }
     }
+
+    delete_mandatory_exams(conn, guide_year, institution, course_code);
+    if let Some(exams) = course.exams.mandatory {
+        for exam in exams {
+            if let Some(code) = exam.code {
+                if let Some(name) = exam.name {
+                    let code: String = code.into();
+                    let name: String = name.into();
+                    if create_exam(conn, &code, &name).is_ok() {
+                        create_mandatory_exam(conn, guide_year, institution, course_code, &code);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn remove_whitespace(s: &str) -> String {
     s.chars().filter(|c| !c.is_whitespace()).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::path::Path;
+
+    use super::config::PolitenessConfig;
+    use super::mock_server::{self, MockServerConfig};
+    use super::*;
+
+    /// Crawls `fixtures/dges` from the mock server, index to courses
+    #[tokio::test]
+    async fn crawls_the_mock_server() {
+        // any free port, the mock server binds it again right away
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        tokio::spawn(mock_server::serve(MockServerConfig {
+            address,
+            fixtures: Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/dges"),
+            faults: Vec::new(),
+            slow_delay: Duration::ZERO,
+        }));
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(address).await.is_ok() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let site = SiteConfig {
+            base_url: Url::parse(&format!("http://{}/guias/", address)).unwrap(),
+            ..SiteConfig::default()
+        };
+        let client = ClientConfig {
+            politeness: PolitenessConfig {
+                default_delay: Duration::ZERO,
+                min_delay: Duration::ZERO,
+                max_delay: Duration::ZERO,
+                respect_crawl_delay: false,
+            },
+            ..ClientConfig::default()
+        };
+        let mut collector = all_courses(site, client, CrawlFilter::default()).await;
+
+        let mut indexes = 0;
+        let mut courses = BTreeSet::new();
+        while let Some(output) = next_output(&mut collector.collector).await {
+            match output {
+                Ok(ScrapeOutcome::Index(_)) => indexes += 1,
+                Ok(ScrapeOutcome::Course(course)) => {
+                    assert_eq!(course.guide_year, Some(2022), "{}", course.course);
+                    assert!(
+                        course.missing_fields().is_empty(),
+                        "{} is missing {:?}",
+                        course.course,
+                        course.missing_fields()
+                    );
+                    courses.insert(course.course.to_string());
+                }
+                Ok(other) => panic!("unexpected {:?}", other),
+                Err(err) => panic!("{}", err),
+            }
+        }
+
+        // the letter index, E and M
+        assert_eq!(indexes, 3);
+        assert_eq!(
+            courses,
+            BTreeSet::from([
+                "0807/9119".to_string(),
+                "0903/9813".to_string(),
+                "1105/9209".to_string()
+            ])
+        );
+        assert!(collector.failed_pages.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,574 @@
+//! Checks an application list: the options a candidate ranks, in order, and how likely they are to
+//! get into each one going by past cutoffs.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use thiserror::Error;
+
+use super::application::{best_application_grade, formulas};
+use super::course_url::CourseUrl;
+use super::db::get_statistics;
+use super::eligibility::{course_eligibility, CourseEligibility, Eligibility};
+use super::grades::{Formula, Grade};
+use super::profile::{CandidateProfile, Preference};
+
+/// How many options an application can have
+pub const MAX_OPTIONS: usize = 6;
+
+/// Points either side of the last placed candidate that could go both ways
+pub const BORDERLINE_POINTS: f64 = 5.0;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PlanWarning {
+    #[error("{0} options, only the first {} count", MAX_OPTIONS)]
+    TooManyOptions(usize),
+    #[error("option {position}: same as option {first}")]
+    Duplicate { position: usize, first: usize },
+    #[error("option {position}: {course} isn't a known course")]
+    UnknownCourse { position: usize, course: String },
+    /// Candidates are placed in the first option they get into
+    #[error("options after {safe} are unlikely to be reached, option {safe} is safe")]
+    AfterSafe { safe: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Likelihood {
+    /// Comfortably above the last placed candidate, or the course didn't fill its vacancies
+    Safe,
+    Borderline,
+    Unlikely,
+    /// Missing exams or under a minimum grade
+    NotEligible,
+    /// No grade, no cutoff to compare it with or no exam requirements known
+    Unknown,
+}
+
+impl Display for Likelihood {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let likelihood = match self {
+            Likelihood::Safe => "safe",
+            Likelihood::Borderline => "borderline",
+            Likelihood::Unlikely => "unlikely",
+            Likelihood::NotEligible => "not eligible",
+            Likelihood::Unknown => "unknown",
+        };
+        write!(f, "{}", likelihood)
+    }
+}
+
+/// How the most recent year of a phase went
+#[derive(Debug, Clone, Copy)]
+pub struct PhaseCutoff {
+    pub year: u16,
+    pub phase: u8,
+    pub last_placed: Option<Grade>,
+    /// Fewer candidates placed than vacancies, anyone eligible got in
+    pub vacancies_left: bool,
+}
+
+impl PhaseCutoff {
+    fn likelihood(&self, grade: Grade) -> Likelihood {
+        if self.vacancies_left {
+            return Likelihood::Safe;
+        }
+        match self.last_placed {
+            Some(last_placed) => {
+                let margin = grade.points() - last_placed.points();
+                if margin >= BORDERLINE_POINTS {
+                    Likelihood::Safe
+                } else if margin > -BORDERLINE_POINTS {
+                    Likelihood::Borderline
+                } else {
+                    Likelihood::Unlikely
+                }
+            }
+            None => Likelihood::Unknown,
+        }
+    }
+}
+
+pub struct PlannedOption {
+    /// From 1
+    pub position: usize,
+    pub(crate) course: CourseUrl,
+    pub eligibility: Option<Eligibility>,
+    pub grade: Option<Grade>,
+    pub minimum: Option<Grade>,
+    /// Phases in order
+    pub cutoffs: Vec<PhaseCutoff>,
+}
+
+impl PlannedOption {
+    /// Against the first phase, where the list is used
+    pub fn likelihood(&self) -> Likelihood {
+        match &self.eligibility {
+            Some(Eligibility::Eligible(_)) => {}
+            Some(Eligibility::Unknown) | None => return Likelihood::Unknown,
+            Some(_) => return Likelihood::NotEligible,
+        }
+        let grade = match self.grade {
+            Some(grade) => grade,
+            None => return Likelihood::Unknown,
+        };
+        if self.minimum.map_or(false, |minimum| grade < minimum) {
+            return Likelihood::NotEligible;
+        }
+        self.phase_likelihood(1).unwrap_or(Likelihood::Unknown)
+    }
+
+    /// `None` without statistics for the phase
+    pub fn phase_likelihood(&self, phase: u8) -> Option<Likelihood> {
+        let grade = self.grade?;
+        let cutoff = self.cutoffs.iter().find(|cutoff| cutoff.phase == phase)?;
+        Some(cutoff.likelihood(grade))
+    }
+}
+
+pub struct ApplicationPlan {
+    pub options: Vec<PlannedOption>,
+    pub warnings: Vec<PlanWarning>,
+}
+
+/// Checks each option in `options`, in order. Duplicates and anything past the sixth option are
+/// warned about and left out
+pub fn plan_application(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    profile: &CandidateProfile,
+    options: &[Preference],
+) -> Result<ApplicationPlan> {
+    let courses: HashMap<CourseUrl, CourseEligibility> =
+        course_eligibility(conn, guide_year, profile)?
+            .into_iter()
+            .map(|course| (course.course.clone(), course))
+            .collect();
+    let formulas = formulas(conn, guide_year)?;
+    let cutoffs = latest_cutoffs(conn)?;
+    Ok(plan_options(options, courses, &formulas, cutoffs, profile))
+}
+
+/// `plan_application` once everything it needs is loaded
+fn plan_options(
+    options: &[Preference],
+    mut courses: HashMap<CourseUrl, CourseEligibility>,
+    formulas: &HashMap<CourseUrl, Formula>,
+    mut cutoffs: HashMap<CourseUrl, Vec<PhaseCutoff>>,
+    profile: &CandidateProfile,
+) -> ApplicationPlan {
+    let mut warnings = Vec::new();
+    if options.len() > MAX_OPTIONS {
+        warnings.push(PlanWarning::TooManyOptions(options.len()));
+    }
+
+    let mut positions: HashMap<CourseUrl, usize> = HashMap::new();
+    let mut planned = Vec::new();
+    for (i, option) in options.iter().take(MAX_OPTIONS).enumerate() {
+        let position = i + 1;
+        let course = CourseUrl::new(&option.institution, &option.course);
+        if let Some(&first) = positions.get(&course) {
+            warnings.push(PlanWarning::Duplicate { position, first });
+            continue;
+        }
+        positions.insert(course.clone(), position);
+
+        let known = courses.remove(&course);
+        if known.is_none() {
+            warnings.push(PlanWarning::UnknownCourse {
+                position,
+                course: course.to_string(),
+            });
+        }
+        let formula = formulas.get(&course).copied().unwrap_or_default();
+        let cutoffs = cutoffs.remove(&course).unwrap_or_default();
+        planned.push(plan_option(
+            position, course, known, &formula, cutoffs, profile,
+        ));
+    }
+
+    warnings.extend(after_safe(&planned));
+    ApplicationPlan {
+        options: planned,
+        warnings,
+    }
+}
+
+fn plan_option(
+    position: usize,
+    course: CourseUrl,
+    known: Option<CourseEligibility>,
+    formula: &Formula,
+    cutoffs: Vec<PhaseCutoff>,
+    profile: &CandidateProfile,
+) -> PlannedOption {
+    let (eligibility, grade, minimum) = match known {
+        Some(known) => {
+            let grade = match &known.eligibility {
+                Eligibility::Eligible(combinations) => {
+                    best_application_grade(formula, combinations, profile).map(|(grade, _)| grade)
+                }
+                _ => None,
+            };
+            (
+                Some(known.eligibility),
+                grade,
+                known.minimum_grades.application,
+            )
+        }
+        None => (None, None, None),
+    };
+    PlannedOption {
+        position,
+        course,
+        eligibility,
+        grade,
+        minimum,
+        cutoffs,
+    }
+}
+
+/// Warns about the options after the first safe one, if there are any
+fn after_safe(options: &[PlannedOption]) -> Option<PlanWarning> {
+    let safe = options
+        .iter()
+        .position(|option| option.likelihood() == Likelihood::Safe)?;
+    if safe + 1 < options.len() {
+        Some(PlanWarning::AfterSafe {
+            safe: options[safe].position,
+        })
+    } else {
+        None
+    }
+}
+
+/// The most recent year of each phase, per course
+fn latest_cutoffs(conn: &mut SqliteConnection) -> Result<HashMap<CourseUrl, Vec<PhaseCutoff>>> {
+    let mut courses: HashMap<CourseUrl, BTreeMap<u8, PhaseCutoff>> = HashMap::new();
+    // the rows come newest year first, so the first one of a phase is the most recent
+    for row in get_statistics(conn)? {
+        let vacancies_left = match (row.vacancies, row.placed) {
+            (Some(vacancies), Some(placed)) => placed < vacancies,
+            _ => false,
+        };
+        courses
+            .entry(CourseUrl::new(&row.institution, &row.course))
+            .or_default()
+            .entry(row.phase as u8)
+            .or_insert(PhaseCutoff {
+                year: row.year as u16,
+                phase: row.phase as u8,
+                last_placed: row
+                    .last_placed_grade
+                    .and_then(|points| points.try_into().ok()),
+                vacancies_left,
+            });
+    }
+    Ok(courses
+        .into_iter()
+        .map(|(course, phases)| (course, phases.into_values().collect()))
+        .collect())
+}
+
+/// One option per line, with the last placed grade and the margin of each phase, then the
+/// warnings
+impl Display for ApplicationPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:>1}  {:<10}  {:>6}  {:<12}  {:<16}  {:<16}  {:<16}",
+            "#", "course", "grade", "likelihood", "1st phase", "2nd phase", "3rd phase"
+        )?;
+        for option in self.options.iter() {
+            let grade = option
+                .grade
+                .map_or("-".to_string(), |grade| grade.to_string());
+            let phases: Vec<String> = (1..=3).map(|phase| phase_column(option, phase)).collect();
+            writeln!(
+                f,
+                "{:>1}  {:<10}  {:>6}  {:<12}  {:<16}  {:<16}  {:<16}",
+                option.position,
+                option.course.to_string(),
+                grade,
+                option.likelihood().to_string(),
+                phases[0],
+                phases[1],
+                phases[2]
+            )?;
+        }
+        for warning in self.warnings.iter() {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// `142.5 +6.3 (2022)`: the last placed grade, how far the candidate is from it and the year
+fn phase_column(option: &PlannedOption, phase: u8) -> String {
+    let cutoff = match option.cutoffs.iter().find(|cutoff| cutoff.phase == phase) {
+        Some(cutoff) => cutoff,
+        None => return "-".to_string(),
+    };
+    match (cutoff.vacancies_left, option.grade, cutoff.last_placed) {
+        (true, _, _) => format!("free ({})", cutoff.year),
+        (false, Some(grade), Some(last_placed)) => format!(
+            "{} {:+.1} ({})",
+            last_placed,
+            grade.points() - last_placed.points(),
+            cutoff.year
+        ),
+        (false, None, Some(last_placed)) => format!("{} ({})", last_placed, cutoff.year),
+        (false, _, None) => format!("- ({})", cutoff.year),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+    use crate::lib::exams::{ExamCode, ExamRequirement};
+    use crate::lib::grades::MinimumGrades;
+
+    fn grade(points: f64) -> Grade {
+        Grade::try_from(points).unwrap()
+    }
+
+    fn cutoff(phase: u8, last_placed: Option<f64>, vacancies_left: bool) -> PhaseCutoff {
+        PhaseCutoff {
+            year: 2022,
+            phase,
+            last_placed: last_placed.map(grade),
+            vacancies_left,
+        }
+    }
+
+    fn option(
+        eligibility: Option<Eligibility>,
+        grade: Option<f64>,
+        minimum: Option<f64>,
+        cutoffs: Vec<PhaseCutoff>,
+    ) -> PlannedOption {
+        PlannedOption {
+            position: 1,
+            course: CourseUrl::new("0807", "9119"),
+            eligibility,
+            grade: grade.map(self::grade),
+            minimum: minimum.map(self::grade),
+            cutoffs,
+        }
+    }
+
+    fn codes(codes: &[&str]) -> BTreeSet<ExamCode> {
+        codes.iter().map(|&code| code.into()).collect()
+    }
+
+    fn eligible() -> Option<Eligibility> {
+        Some(Eligibility::Eligible(vec![codes(&["19"])]))
+    }
+
+    fn course(code: &str) -> CourseUrl {
+        let preference: Preference = code.parse().unwrap();
+        CourseUrl::new(&preference.institution, &preference.course)
+    }
+
+    /// 15 of secondary average and 150 in 19 make 150 at every course
+    fn profile() -> CandidateProfile {
+        CandidateProfile {
+            name: None,
+            exams: HashMap::from([("19".to_string(), grade(150.0))]),
+            secondary_average: Some(15.0),
+            contingent: None,
+            preferences: Vec::new(),
+        }
+    }
+
+    const COURSES: [&str; 3] = ["0807/9119", "0903/9813", "1105/9209"];
+
+    /// Plans `options` against `COURSES`, which only ask for 19, with the last placed grade of
+    /// the first phase of some of them
+    fn plan(options: &[&str], last_placed: &[(&str, f64)]) -> ApplicationPlan {
+        let courses = COURSES
+            .iter()
+            .map(|&code| {
+                let known = CourseEligibility {
+                    course: course(code),
+                    requirement: Some(ExamRequirement::Exam("19".into())),
+                    minimum_grades: MinimumGrades::default(),
+                    eligibility: eligible().unwrap(),
+                };
+                (course(code), known)
+            })
+            .collect();
+        let formulas = COURSES
+            .iter()
+            .map(|&code| {
+                let formula = Formula {
+                    secondary_average: Some(50),
+                    exams: Some(50),
+                };
+                (course(code), formula)
+            })
+            .collect();
+        let cutoffs = last_placed
+            .iter()
+            .map(|&(code, points)| (course(code), vec![cutoff(1, Some(points), false)]))
+            .collect();
+        let options: Vec<Preference> = options.iter().map(|code| code.parse().unwrap()).collect();
+        plan_options(&options, courses, &formulas, cutoffs, &profile())
+    }
+
+    #[test]
+    fn safe_from_borderline_points_above() {
+        assert_eq!(
+            cutoff(1, Some(145.0), false).likelihood(grade(150.0)),
+            Likelihood::Safe
+        );
+        assert_eq!(
+            cutoff(1, Some(145.1), false).likelihood(grade(150.0)),
+            Likelihood::Borderline
+        );
+    }
+
+    #[test]
+    fn unlikely_from_borderline_points_below() {
+        assert_eq!(
+            cutoff(1, Some(154.9), false).likelihood(grade(150.0)),
+            Likelihood::Borderline
+        );
+        assert_eq!(
+            cutoff(1, Some(155.0), false).likelihood(grade(150.0)),
+            Likelihood::Unlikely
+        );
+    }
+
+    #[test]
+    fn safe_when_vacancies_were_left() {
+        assert_eq!(
+            cutoff(1, Some(190.0), true).likelihood(grade(100.0)),
+            Likelihood::Safe
+        );
+        assert_eq!(
+            cutoff(1, None, true).likelihood(grade(100.0)),
+            Likelihood::Safe
+        );
+    }
+
+    #[test]
+    fn unknown_without_a_last_placed_grade() {
+        assert_eq!(
+            cutoff(1, None, false).likelihood(grade(150.0)),
+            Likelihood::Unknown
+        );
+    }
+
+    #[test]
+    fn option_likelihood_goes_by_the_first_phase() {
+        let cutoffs = vec![cutoff(1, Some(140.0), false), cutoff(2, Some(160.0), false)];
+        let option = option(eligible(), Some(150.0), None, cutoffs);
+        assert_eq!(option.likelihood(), Likelihood::Safe);
+        assert_eq!(option.phase_likelihood(2), Some(Likelihood::Unlikely));
+        assert_eq!(option.phase_likelihood(3), None);
+    }
+
+    #[test]
+    fn option_not_eligible() {
+        let cutoffs = vec![cutoff(1, Some(140.0), false)];
+        let not_eligible = Eligibility::NotEligible {
+            combination: codes(&["19"]),
+            missing: codes(&["19"]),
+            below_minimum: BTreeSet::new(),
+            application_grade: None,
+        };
+        assert_eq!(
+            option(Some(not_eligible), None, None, cutoffs.clone()).likelihood(),
+            Likelihood::NotEligible
+        );
+        // under the minimum application grade
+        assert_eq!(
+            option(eligible(), Some(150.0), Some(155.0), cutoffs).likelihood(),
+            Likelihood::NotEligible
+        );
+    }
+
+    #[test]
+    fn option_unknown() {
+        let cutoffs = vec![cutoff(1, Some(140.0), false)];
+        assert_eq!(
+            option(None, None, None, cutoffs.clone()).likelihood(),
+            Likelihood::Unknown
+        );
+        assert_eq!(
+            option(Some(Eligibility::Unknown), None, None, cutoffs.clone()).likelihood(),
+            Likelihood::Unknown
+        );
+        assert_eq!(
+            option(eligible(), None, None, cutoffs).likelihood(),
+            Likelihood::Unknown
+        );
+        assert_eq!(
+            option(
+                eligible(),
+                Some(150.0),
+                None,
+                vec![cutoff(2, Some(140.0), false)]
+            )
+            .likelihood(),
+            Likelihood::Unknown
+        );
+    }
+
+    #[test]
+    fn warns_about_too_many_options_and_duplicates() {
+        let plan = plan(
+            &[
+                "0807/9119",
+                "0807/9119",
+                "0903/9813",
+                "9999/9999",
+                "0807/9119",
+                "1105/9209",
+                "0903/9813",
+            ],
+            &[],
+        );
+        assert_eq!(
+            plan.warnings,
+            vec![
+                PlanWarning::TooManyOptions(7),
+                PlanWarning::Duplicate {
+                    position: 2,
+                    first: 1
+                },
+                PlanWarning::UnknownCourse {
+                    position: 4,
+                    course: "9999/9999".to_string()
+                },
+                PlanWarning::Duplicate {
+                    position: 5,
+                    first: 1
+                },
+            ]
+        );
+        let positions: Vec<_> = plan.options.iter().map(|option| option.position).collect();
+        assert_eq!(positions, vec![1, 3, 4, 6]);
+        assert_eq!(plan.options[0].grade, Some(grade(150.0)));
+        assert!(plan.options[2].eligibility.is_none());
+    }
+
+    #[test]
+    fn warns_about_options_after_a_safe_one() {
+        let plan = plan(
+            &["0807/9119", "0903/9813", "1105/9209"],
+            &[("0807/9119", 160.0), ("0903/9813", 140.0)],
+        );
+        assert_eq!(plan.warnings, vec![PlanWarning::AfterSafe { safe: 2 }]);
+
+        let plan = plan(
+            &["0807/9119", "0903/9813"],
+            &[("0807/9119", 160.0), ("0903/9813", 140.0)],
+        );
+        assert!(plan.warnings.is_empty());
+    }
+}
@@ -0,0 +1,465 @@
+//! Candidate profiles: the exams a student took, their secondary school average and what they're
+//! after. Counsellors keep one file per student, in TOML or JSON, and every tool reads the same one.
+//!
+//! ```toml
+//! version = 1
+//! name = "Ana"
+//! secondary_average = 16.4
+//! contingent = "madeira"
+//!
+//! [[exams]]
+//! code = "19"
+//! grade = 152
+//! year = 2022
+//!
+//! [[preferences]]
+//! institution = "0807"
+//! course = "9119"
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use chrono::Datelike;
+use diesel::SqliteConnection;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::db::get_exam_codes;
+use super::exams::ExamCode;
+use super::grades::Grade;
+
+/// The only version there is so far. Bump it when the format changes in a way old files can't be
+/// read with
+pub const PROFILE_VERSION: u32 = 1;
+
+/// A profile file, as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileFile {
+    pub version: u32,
+    pub name: Option<String>,
+    /// From 0 to 20
+    pub secondary_average: Option<f64>,
+    #[serde(default)]
+    pub exams: Vec<ExamResult>,
+    pub contingent: Option<RegionalContingent>,
+    /// Course/institution pairs the student is interested in, favourite first
+    #[serde(default)]
+    pub preferences: Vec<Preference>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExamResult {
+    /// Official code, `"19"` for Matemática A
+    pub code: String,
+    /// From 0 to 200
+    pub grade: f64,
+    pub year: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionalContingent {
+    Azores,
+    Madeira,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preference {
+    pub institution: String,
+    pub course: String,
+}
+
+/// `0807/9119`, like the "Código" field on the course page
+impl FromStr for Preference {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once('/') {
+            Some((institution, course))
+                if !institution.trim().is_empty() && !course.trim().is_empty() =>
+            {
+                Ok(Preference {
+                    institution: institution.trim().to_string(),
+                    course: course.trim().to_string(),
+                })
+            }
+            _ => bail!("\"{}\" isn't an institution/course pair", s.trim()),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ProfileError {
+    #[error(
+        "version {0} isn't supported, this build reads version {}",
+        PROFILE_VERSION
+    )]
+    UnsupportedVersion(u32),
+    #[error("exam {code}: no exam with this code in the catalogue")]
+    UnknownExam { code: String },
+    #[error("exam {code}: grade {grade} is outside 0-200")]
+    GradeOutOfRange { code: String, grade: f64 },
+    #[error("exam {code}: {year} is in the future")]
+    FutureYear { code: String, year: i32 },
+    #[error("exam {code}: taken twice in {year}")]
+    DuplicateExam { code: String, year: i32 },
+    #[error("secondary average {0} is outside 0-20")]
+    SecondaryAverageOutOfRange(f64),
+    #[error("preference {institution}/{course} is listed twice")]
+    DuplicatePreference { institution: String, course: String },
+}
+
+/// Everything wrong with a profile, not just the first thing
+#[derive(Error, Debug)]
+pub struct ProfileErrors(pub Vec<ProfileError>);
+
+impl Display for ProfileErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl ProfileFile {
+    /// `.toml` or `.json`, going by the extension
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Ok(toml::from_str(&contents)?),
+            Some("json") => Ok(serde_json::from_str(&contents)?),
+            _ => bail!("{}: profiles are .toml or .json files", path.display()),
+        }
+    }
+
+    /// Checks the profile against the exam codes in `catalogue`
+    pub fn validate(&self, catalogue: &HashSet<String>) -> Vec<ProfileError> {
+        let mut errors = Vec::new();
+        if self.version != PROFILE_VERSION {
+            errors.push(ProfileError::UnsupportedVersion(self.version));
+        }
+        if let Some(average) = self.secondary_average {
+            if !(0.0..=20.0).contains(&average) {
+                errors.push(ProfileError::SecondaryAverageOutOfRange(average));
+            }
+        }
+
+        let this_year = chrono::Utc::now().year();
+        let mut taken = HashSet::new();
+        for exam in self.exams.iter() {
+            let code = exam.code();
+            if !catalogue.contains(&code) {
+                errors.push(ProfileError::UnknownExam { code: code.clone() });
+            }
+            if Grade::try_from(exam.grade).is_err() {
+                errors.push(ProfileError::GradeOutOfRange {
+                    code: code.clone(),
+                    grade: exam.grade,
+                });
+            }
+            if exam.year > this_year {
+                errors.push(ProfileError::FutureYear {
+                    code: code.clone(),
+                    year: exam.year,
+                });
+            }
+            if !taken.insert((code.clone(), exam.year)) {
+                errors.push(ProfileError::DuplicateExam {
+                    code,
+                    year: exam.year,
+                });
+            }
+        }
+
+        let mut preferences = HashSet::new();
+        for preference in self.preferences.iter() {
+            if !preferences.insert(preference) {
+                errors.push(ProfileError::DuplicatePreference {
+                    institution: preference.institution.clone(),
+                    course: preference.course.clone(),
+                });
+            }
+        }
+        errors
+    }
+}
+
+impl ExamResult {
+    /// Codes are two digits, `7` is `07`
+    fn code(&self) -> String {
+        format!("{:0>2}", self.code.trim())
+    }
+}
+
+/// A profile that passed validation, the way the tools use it
+#[derive(Debug)]
+pub struct CandidateProfile {
+    pub name: Option<String>,
+    /// Best grade in each exam taken, by exam code
+    pub exams: HashMap<String, Grade>,
+    /// From 0 to 20
+    pub secondary_average: Option<f64>,
+    pub contingent: Option<RegionalContingent>,
+    pub preferences: Vec<Preference>,
+}
+
+impl CandidateProfile {
+    /// Reads a profile file and validates it against the exams in the database
+    pub fn load(path: &Path, conn: &mut SqliteConnection) -> Result<Self> {
+        let file = ProfileFile::read(path)?;
+        let catalogue: HashSet<String> = get_exam_codes(conn)?.into_iter().collect();
+        Ok(CandidateProfile::try_from_file(file, &catalogue)?)
+    }
+
+    pub fn try_from_file(
+        file: ProfileFile,
+        catalogue: &HashSet<String>,
+    ) -> Result<Self, ProfileErrors> {
+        let errors = file.validate(catalogue);
+        if !errors.is_empty() {
+            return Err(ProfileErrors(errors));
+        }
+
+        let mut exams: HashMap<String, Grade> = HashMap::new();
+        for exam in file.exams.iter() {
+            // validated above
+            let grade = Grade::try_from(exam.grade).unwrap();
+            let best = exams.entry(exam.code()).or_insert(grade);
+            if grade > *best {
+                *best = grade;
+            }
+        }
+
+        Ok(CandidateProfile {
+            name: file.name,
+            exams,
+            secondary_average: file.secondary_average,
+            contingent: file.contingent,
+            preferences: file.preferences,
+        })
+    }
+
+    pub fn grade(&self, exam: &ExamCode) -> Option<Grade> {
+        self.exams.get(&exam.to_string()).copied()
+    }
+
+    pub(crate) fn took(&self, exam: &ExamCode) -> bool {
+        self.grade(exam).is_some()
+    }
+
+    /// The exams taken with at least `minimum`
+    pub(crate) fn passed(&self, minimum: Option<Grade>) -> HashSet<ExamCode> {
+        self.exams
+            .iter()
+            .filter(|(_, grade)| minimum.map_or(true, |minimum| **grade >= minimum))
+            .map(|(code, _)| code.as_str().into())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalogue() -> HashSet<String> {
+        ["02", "07", "19"]
+            .iter()
+            .map(|code| code.to_string())
+            .collect()
+    }
+
+    fn errors(toml: &str) -> Vec<ProfileError> {
+        toml::from_str::<ProfileFile>(toml)
+            .unwrap()
+            .validate(&catalogue())
+    }
+
+    fn grade(points: f64) -> Grade {
+        Grade::try_from(points).unwrap()
+    }
+
+    fn example(extension: &str) -> ProfileFile {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/profiles/example")
+            .with_extension(extension);
+        ProfileFile::read(&path).unwrap()
+    }
+
+    #[test]
+    fn unsupported_version() {
+        assert_eq!(
+            errors("version = 2"),
+            vec![ProfileError::UnsupportedVersion(2)]
+        );
+    }
+
+    #[test]
+    fn secondary_average_out_of_range() {
+        assert_eq!(
+            errors("version = 1\nsecondary_average = 20.5"),
+            vec![ProfileError::SecondaryAverageOutOfRange(20.5)]
+        );
+    }
+
+    #[test]
+    fn unknown_exam() {
+        assert_eq!(
+            errors("version = 1\n[[exams]]\ncode = \"99\"\ngrade = 150\nyear = 2022"),
+            vec![ProfileError::UnknownExam {
+                code: "99".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn grade_out_of_range() {
+        assert_eq!(
+            errors("version = 1\n[[exams]]\ncode = \"19\"\ngrade = 200.5\nyear = 2022"),
+            vec![ProfileError::GradeOutOfRange {
+                code: "19".to_string(),
+                grade: 200.5
+            }]
+        );
+    }
+
+    #[test]
+    fn future_year() {
+        let next_year = chrono::Utc::now().year() + 1;
+        assert_eq!(
+            errors(&format!(
+                "version = 1\n[[exams]]\ncode = \"19\"\ngrade = 150\nyear = {}",
+                next_year
+            )),
+            vec![ProfileError::FutureYear {
+                code: "19".to_string(),
+                year: next_year
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_exam_after_padding() {
+        assert_eq!(
+            errors(
+                "version = 1
+                [[exams]]
+                code = \"07\"
+                grade = 130
+                year = 2022
+                [[exams]]
+                code = \"7\"
+                grade = 140
+                year = 2022"
+            ),
+            vec![ProfileError::DuplicateExam {
+                code: "07".to_string(),
+                year: 2022
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_preference() {
+        assert_eq!(
+            errors(
+                "version = 1
+                [[preferences]]
+                institution = \"0807\"
+                course = \"9119\"
+                [[preferences]]
+                institution = \"0807\"
+                course = \"9119\""
+            ),
+            vec![ProfileError::DuplicatePreference {
+                institution: "0807".to_string(),
+                course: "9119".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn every_error_at_once() {
+        assert_eq!(
+            errors(
+                "version = 2
+                secondary_average = -1
+                [[exams]]
+                code = \"99\"
+                grade = -5
+                year = 2022"
+            ),
+            vec![
+                ProfileError::UnsupportedVersion(2),
+                ProfileError::SecondaryAverageOutOfRange(-1.0),
+                ProfileError::UnknownExam {
+                    code: "99".to_string()
+                },
+                ProfileError::GradeOutOfRange {
+                    code: "99".to_string(),
+                    grade: -5.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pads_exam_codes() {
+        let file: ProfileFile =
+            toml::from_str("version = 1\n[[exams]]\ncode = \"7\"\ngrade = 131\nyear = 2022")
+                .unwrap();
+        let profile = CandidateProfile::try_from_file(file, &catalogue()).unwrap();
+        assert_eq!(
+            profile.exams,
+            HashMap::from([("07".to_string(), grade(131.0))])
+        );
+    }
+
+    #[test]
+    fn keeps_the_best_grade_per_exam() {
+        let profile = CandidateProfile::try_from_file(example("toml"), &catalogue()).unwrap();
+        assert_eq!(
+            profile.exams,
+            HashMap::from([
+                ("07".to_string(), grade(144.0)),
+                ("19".to_string(), grade(152.0))
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_profiles_are_rejected() {
+        let file: ProfileFile = toml::from_str("version = 2").unwrap();
+        let errors = CandidateProfile::try_from_file(file, &catalogue()).unwrap_err();
+        assert_eq!(errors.0, vec![ProfileError::UnsupportedVersion(2)]);
+    }
+
+    #[test]
+    fn example_profiles_parse() {
+        let toml = example("toml");
+        assert!(toml.validate(&catalogue()).is_empty());
+        assert_eq!(toml.name.as_deref(), Some("Example candidate"));
+        assert_eq!(toml.exams.len(), 3);
+        assert_eq!(toml.preferences.len(), 2);
+
+        let json = example("json");
+        assert!(json.validate(&catalogue()).is_empty());
+        assert_eq!(json.contingent, Some(RegionalContingent::Madeira));
+        assert_eq!(json.exams.len(), 2);
+        assert_eq!(
+            json.preferences,
+            vec!["0903/9813".parse::<Preference>().unwrap()]
+        );
+    }
+}
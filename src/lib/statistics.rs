@@ -0,0 +1,154 @@
+//! "Dados Estatísticos de Candidaturas Anteriores": how past application phases went.
+
+use anyhow::{bail, Result};
+use voyager::scraper::{ElementRef, Selector};
+
+use super::diagnostics::ParseDiagnostic;
+use super::grades::Grade;
+use super::unrecognized::UnrecognizedContent;
+
+/// One row of the table
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PhaseStatistics {
+    pub(crate) year: u16,
+    /// 1, 2 or 3
+    pub(crate) phase: u8,
+    pub(crate) vacancies: Option<u16>,
+    pub(crate) applicants: Option<u16>,
+    pub(crate) placed: Option<u16>,
+    pub(crate) last_placed_grade: Option<Grade>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Year,
+    Phase,
+    Vacancies,
+    Applicants,
+    Placed,
+    LastPlacedGrade,
+}
+
+impl Column {
+    fn from_header(header: &str) -> Option<Self> {
+        match header.to_lowercase().as_str() {
+            "ano" => Some(Column::Year),
+            "fase" => Some(Column::Phase),
+            "vagas" => Some(Column::Vacancies),
+            "candidatos" => Some(Column::Applicants),
+            "colocados" => Some(Column::Placed),
+            "nota do último colocado" => Some(Column::LastPlacedGrade),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the tables up to the next section, with the column names on their first row
+pub(crate) fn statistics_section(
+    header: ElementRef,
+    unrecognized: &mut Vec<UnrecognizedContent>,
+    diagnostics: &mut Vec<ParseDiagnostic>,
+) -> Vec<PhaseStatistics> {
+    let row_selector = Selector::parse("tr").unwrap();
+    let cell_selector = Selector::parse("th, td").unwrap();
+
+    let mut statistics = Vec::new();
+    for node in header.next_siblings() {
+        let table = match ElementRef::wrap(node) {
+            Some(element) if element.value().name() == "h2" => break,
+            Some(element) if element.value().name() == "table" => element,
+            _ => continue,
+        };
+
+        let mut rows = table.select(&row_selector);
+        let columns: Vec<(String, Option<Column>)> = match rows.next() {
+            Some(row) => row
+                .select(&cell_selector)
+                .map(|cell| {
+                    let name = cell_text(cell);
+                    let column = Column::from_header(&name);
+                    if column.is_none() {
+                        unrecognized.push(UnrecognizedContent::field(*cell, &name, ""));
+                    }
+                    (name, column)
+                })
+                .collect(),
+            None => continue,
+        };
+
+        for row in rows {
+            let (mut year, mut phase, mut vacancies, mut applicants, mut placed) =
+                (None, None, None, None, None);
+            let mut last_placed_grade = None;
+            for ((name, column), cell) in columns.iter().zip(row.select(&cell_selector)) {
+                let value = cell_text(cell);
+                let parsed = match column {
+                    Some(Column::Year) => parse_number(&value).map(|value| year = value),
+                    Some(Column::Phase) => parse_phase(&value).map(|value| phase = value),
+                    Some(Column::Vacancies) => parse_number(&value).map(|value| vacancies = value),
+                    Some(Column::Applicants) => {
+                        parse_number(&value).map(|value| applicants = value)
+                    }
+                    Some(Column::Placed) => parse_number(&value).map(|value| placed = value),
+                    Some(Column::LastPlacedGrade) => {
+                        parse_grade(&value).map(|value| last_placed_grade = value)
+                    }
+                    None => Ok(()),
+                };
+                if let Err(err) = parsed {
+                    diagnostics.push(ParseDiagnostic::new("statistics", name, &value, err));
+                }
+            }
+            // without them the row can't be told apart from the others
+            if let (Some(year), Some(phase)) = (year, phase) {
+                statistics.push(PhaseStatistics {
+                    year,
+                    phase,
+                    vacancies,
+                    applicants,
+                    placed,
+                    last_placed_grade,
+                });
+            }
+        }
+    }
+    statistics
+}
+
+fn cell_text(cell: ElementRef) -> String {
+    cell.text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Empty cells and dashes mean there's no value
+fn is_blank(value: &str) -> bool {
+    matches!(value, "" | "-" | "--")
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<Option<T>> {
+    if is_blank(value) {
+        return Ok(None);
+    }
+    match value.replace('.', "").parse::<T>() {
+        Ok(number) => Ok(Some(number)),
+        Err(_) => bail!("\"{}\" isn't a whole number", value),
+    }
+}
+
+/// `1ª` or `1ª Fase`
+fn parse_phase(value: &str) -> Result<Option<u8>> {
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+    match digits.parse::<u8>() {
+        Ok(phase) if (1..=3).contains(&phase) => Ok(Some(phase)),
+        _ => bail!("\"{}\" isn't a phase", value),
+    }
+}
+
+fn parse_grade(value: &str) -> Result<Option<Grade>> {
+    if is_blank(value) {
+        return Ok(None);
+    }
+    Ok(Some(value.parse::<Grade>()?))
+}
@@ -0,0 +1,193 @@
+//! How competitive courses have become: the last placed grade, vacancies filled and applicants per
+//! vacancy of a phase over the years, from every run so far.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use serde::Serialize;
+
+use super::course_url::CourseUrl;
+use super::db::get_statistics_series;
+use super::profile::Preference;
+
+/// One year of a phase
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendPoint {
+    pub year: u16,
+    pub vacancies: Option<u16>,
+    pub applicants: Option<u16>,
+    pub placed: Option<u16>,
+    pub last_placed_grade: Option<f64>,
+}
+
+impl TrendPoint {
+    /// Placed candidates per vacancy
+    pub fn fill_rate(&self) -> Option<f64> {
+        ratio(self.placed?, self.vacancies?)
+    }
+
+    /// Applicants per vacancy
+    pub fn demand(&self) -> Option<f64> {
+        ratio(self.applicants?, self.vacancies?)
+    }
+}
+
+fn ratio(numerator: u16, denominator: u16) -> Option<f64> {
+    if denominator == 0 {
+        None
+    } else {
+        Some(f64::from(numerator) / f64::from(denominator))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseTrend {
+    pub institution: String,
+    pub course: String,
+    pub phase: u8,
+    /// Oldest year first
+    pub years: Vec<TrendPoint>,
+    /// Points between the last placed grade of the first and last years that have one
+    pub cutoff_change: Option<f64>,
+    /// Change in applicants per vacancy between the first and last years that have them, 0.1 is
+    /// 10% more
+    pub demand_change: Option<f64>,
+    /// Of the most recent year
+    pub fill_rate: Option<f64>,
+}
+
+impl CourseTrend {
+    fn new(course: &CourseUrl, phase: u8, years: Vec<TrendPoint>) -> Self {
+        let cutoffs: Vec<f64> = years
+            .iter()
+            .filter_map(|point| point.last_placed_grade)
+            .collect();
+        let cutoff_change = match (cutoffs.first(), cutoffs.last()) {
+            (Some(first), Some(last)) if cutoffs.len() > 1 => Some(last - first),
+            _ => None,
+        };
+        let demand: Vec<f64> = years.iter().filter_map(TrendPoint::demand).collect();
+        let demand_change = match (demand.first(), demand.last()) {
+            (Some(first), Some(last)) if demand.len() > 1 && *first > 0.0 => {
+                Some(last / first - 1.0)
+            }
+            _ => None,
+        };
+        CourseTrend {
+            institution: course.institution_code().to_string(),
+            course: course.course_code().to_string(),
+            phase,
+            fill_rate: years.last().and_then(TrendPoint::fill_rate),
+            years,
+            cutoff_change,
+            demand_change,
+        }
+    }
+}
+
+/// Every course with statistics for `phase`, or only `courses` if there are any
+pub fn course_trends(
+    conn: &mut SqliteConnection,
+    phase: u8,
+    courses: &[Preference],
+) -> Result<Vec<CourseTrend>> {
+    let wanted: Vec<CourseUrl> = courses
+        .iter()
+        .map(|course| CourseUrl::new(&course.institution, &course.course))
+        .collect();
+
+    let mut series: BTreeMap<CourseUrl, Vec<TrendPoint>> = BTreeMap::new();
+    for row in get_statistics_series(conn, i32::from(phase))? {
+        let course = CourseUrl::new(&row.institution, &row.course);
+        if !wanted.is_empty() && !wanted.contains(&course) {
+            continue;
+        }
+        series.entry(course).or_default().push(TrendPoint {
+            year: row.year as u16,
+            vacancies: row.vacancies.and_then(|value| value.try_into().ok()),
+            applicants: row.applicants.and_then(|value| value.try_into().ok()),
+            placed: row.placed.and_then(|value| value.try_into().ok()),
+            last_placed_grade: row.last_placed_grade,
+        });
+    }
+
+    Ok(series
+        .into_iter()
+        .map(|(course, years)| CourseTrend::new(&course, phase, years))
+        .collect())
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map_or("-".to_string(), |value| value.to_string())
+}
+
+/// A line per course: first and last year, how the cutoff and demand moved and how full the
+/// course was the last year
+pub fn trend_summary_table(trends: &[CourseTrend]) -> String {
+    let mut table = format!(
+        "{:<10}  {:<9}  {:>15}  {:>8}  {:>6}  {:>6}\n",
+        "course", "years", "last placed", "change", "demand", "filled"
+    );
+    for trend in trends {
+        let years = match (trend.years.first(), trend.years.last()) {
+            (Some(first), Some(last)) if first.year != last.year => {
+                format!("{}-{}", first.year, last.year)
+            }
+            (Some(first), _) => first.year.to_string(),
+            _ => "-".to_string(),
+        };
+        let cutoffs: Vec<f64> = trend
+            .years
+            .iter()
+            .filter_map(|point| point.last_placed_grade)
+            .collect();
+        let last_placed = match (cutoffs.first(), cutoffs.last()) {
+            (Some(first), Some(last)) if cutoffs.len() > 1 => format!("{} -> {}", first, last),
+            (Some(first), _) => first.to_string(),
+            _ => "-".to_string(),
+        };
+        table.push_str(&format!(
+            "{:<10}  {:<9}  {:>15}  {:>8}  {:>6}  {:>6}\n",
+            format!("{}/{}", trend.institution, trend.course),
+            years,
+            last_placed,
+            optional(trend.cutoff_change.map(|change| format!("{:+.1}", change))),
+            optional(
+                trend
+                    .demand_change
+                    .map(|change| format!("{:+.0}%", change * 100.0))
+            ),
+            optional(trend.fill_rate.map(|rate| format!("{:.0}%", rate * 100.0))),
+        ));
+    }
+    table
+}
+
+/// Every year of every course, oldest first
+pub fn trend_table(trends: &[CourseTrend]) -> String {
+    let mut table = format!(
+        "{:<10}  {:>4}  {:>9}  {:>10}  {:>6}  {:>11}  {:>6}  {:>6}\n",
+        "course", "year", "vacancies", "applicants", "placed", "last placed", "demand", "filled"
+    );
+    for trend in trends {
+        for point in trend.years.iter() {
+            table.push_str(&format!(
+                "{:<10}  {:>4}  {:>9}  {:>10}  {:>6}  {:>11}  {:>6}  {:>6}\n",
+                format!("{}/{}", trend.institution, trend.course),
+                point.year,
+                optional(point.vacancies),
+                optional(point.applicants),
+                optional(point.placed),
+                optional(point.last_placed_grade),
+                optional(point.demand().map(|demand| format!("{:.1}", demand))),
+                optional(
+                    point
+                        .fill_rate()
+                        .map(|rate| format!("{:.0}%", rate * 100.0))
+                ),
+            ));
+        }
+    }
+    table
+}
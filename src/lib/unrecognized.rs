@@ -0,0 +1,80 @@
+use ego_tree::NodeRef;
+use voyager::scraper::{ElementRef, Node};
+
+/// How much html is kept around unrecognized content
+const SNIPPET_LENGTH: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ContentKind {
+    /// A section header (`<h2>`) we don't know
+    Header,
+    /// A `Field: value` line we don't know
+    Field,
+}
+
+impl ContentKind {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ContentKind::Header => "header",
+            ContentKind::Field => "field",
+        }
+    }
+}
+
+/// Something on a course page the scraper doesn't know what to do with yet
+#[derive(Debug, Clone)]
+pub(crate) struct UnrecognizedContent {
+    pub(crate) kind: ContentKind,
+    pub(crate) name: String,
+    pub(crate) raw_value: Option<String>,
+    pub(crate) snippet: String,
+}
+
+impl UnrecognizedContent {
+    pub(crate) fn header(header: ElementRef) -> Self {
+        UnrecognizedContent {
+            kind: ContentKind::Header,
+            name: header.inner_html(),
+            raw_value: None,
+            snippet: snippet(*header),
+        }
+    }
+
+    pub(crate) fn field(node: NodeRef<Node>, name: &str, raw_value: &str) -> Self {
+        UnrecognizedContent {
+            kind: ContentKind::Field,
+            name: name.to_string(),
+            raw_value: Some(raw_value.to_string()),
+            snippet: snippet(node),
+        }
+    }
+}
+
+/// The html of `node` and what follows it, up to the next section header
+fn snippet(node: NodeRef<Node>) -> String {
+    let mut snippet = String::new();
+    for (i, node) in std::iter::once(node)
+        .chain(node.next_siblings())
+        .enumerate()
+    {
+        match node.value() {
+            Node::Text(text) => snippet.push_str(text),
+            Node::Element(element) => {
+                if i > 0 && element.name() == "h2" {
+                    break;
+                }
+                if let Some(element) = ElementRef::wrap(node) {
+                    snippet.push_str(&element.html());
+                }
+            }
+            _ => {}
+        }
+        if snippet.len() >= SNIPPET_LENGTH {
+            break;
+        }
+    }
+    match snippet.char_indices().nth(SNIPPET_LENGTH) {
+        Some((end, _)) => snippet[..end].to_string(),
+        None => snippet,
+    }
+}
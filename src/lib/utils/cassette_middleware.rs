@@ -0,0 +1,214 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Request, ResponseBuilderExt,
+};
+use reqwest_middleware::{Error, Middleware, Next};
+use serde::{Deserialize, Serialize};
+use task_local_extensions::Extensions;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Every request goes through, and the response is saved to the cassette directory
+    Record,
+    /// Requests are answered from the cassette directory. Unknown requests fail
+    Replay,
+}
+
+/// Records request/response pairs to cassette files, or replays them.
+///
+/// Each pair is stored as `<key>.json` (status and headers) plus `<key>.body` (raw bytes, still
+/// Windows-1252). The key is built from the method, path and query, but not the host, so a
+/// cassette recorded against DGES can be replayed against any base url.
+pub struct CassetteMiddleware {
+    pub mode: CassetteMode,
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cassette {
+    method: String,
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl CassetteMiddleware {
+    pub fn new(mode: CassetteMode, directory: PathBuf) -> Self {
+        Self { mode, directory }
+    }
+
+    fn cassette_path(&self, request: &Request) -> PathBuf {
+        let url = request.url();
+        let mut key = format!("{}{}", request.method(), url.path());
+        if let Some(query) = url.query() {
+            key.push('?');
+            key.push_str(query);
+        }
+        // percent-encoded so different requests never share a file. Dots too, or `with_extension`
+        // would cut the key short
+        let key: String = key
+            .bytes()
+            .map(|byte| {
+                if byte.is_ascii_alphanumeric() || byte == b'-' {
+                    char::from(byte).to_string()
+                } else {
+                    format!("%{:02X}", byte)
+                }
+            })
+            .collect();
+        self.directory.join(key)
+    }
+
+    async fn record(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let path = self.cassette_path(&req);
+        let method = req.method().to_string();
+        let response = next.run(req, extensions).await?;
+
+        let url = response.url().clone();
+        let status = response.status();
+        let version = response.version();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        let cassette = Cassette {
+            method,
+            url: url.to_string(),
+            status: status.as_u16(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect(),
+        };
+        write_cassette(&path, &cassette, &body).map_err(Error::Middleware)?;
+        info!("Recorded {} into {}", url, path.display());
+
+        let mut builder = http::Response::builder()
+            .status(status)
+            .version(version)
+            .url(url);
+        if let Some(builder_headers) = builder.headers_mut() {
+            *builder_headers = headers;
+        }
+        let response = builder.body(body).map_err(Error::middleware)?;
+        Ok(response.into())
+    }
+
+    fn replay(&self, req: Request) -> reqwest_middleware::Result<reqwest::Response> {
+        let path = self.cassette_path(&req);
+        let (cassette, body) = read_cassette(&path).map_err(|err| {
+            Error::Middleware(anyhow!(
+                "No cassette for {} {} ({}): {}",
+                req.method(),
+                req.url(),
+                path.display(),
+                err
+            ))
+        })?;
+
+        let mut builder = http::Response::builder()
+            .status(cassette.status)
+            .url(req.url().clone());
+        for (name, value) in cassette.headers {
+            builder = builder.header(
+                HeaderName::from_bytes(name.as_bytes()).map_err(Error::middleware)?,
+                HeaderValue::from_str(&value).map_err(Error::middleware)?,
+            );
+        }
+        let response = builder.body(body).map_err(Error::middleware)?;
+        Ok(response.into())
+    }
+}
+
+fn write_cassette(path: &Path, cassette: &Cassette, body: &[u8]) -> anyhow::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(
+        path.with_extension("json"),
+        serde_json::to_string_pretty(cassette)?,
+    )?;
+    fs::write(path.with_extension("body"), body)?;
+    Ok(())
+}
+
+fn read_cassette(path: &Path) -> anyhow::Result<(Cassette, Vec<u8>)> {
+    let cassette = serde_json::from_slice(&fs::read(path.with_extension("json"))?)?;
+    let body = fs::read(path.with_extension("body"))?;
+    Ok((cassette, body))
+}
+
+#[async_trait::async_trait]
+impl Middleware for CassetteMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        match self.mode {
+            CassetteMode::Record => self.record(req, extensions, next).await,
+            CassetteMode::Replay => self.replay(req),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Method, Url};
+
+    use super::*;
+
+    fn cassette_path(url: &str) -> PathBuf {
+        let middleware = CassetteMiddleware::new(CassetteMode::Replay, PathBuf::from("cassettes"));
+        middleware.cassette_path(&Request::new(Method::GET, Url::parse(url).unwrap()))
+    }
+
+    #[test]
+    fn different_requests_get_different_cassettes() {
+        let urls = [
+            "https://www.dges.gov.pt/guias/indcurso.asp?letra=E",
+            "https://www.dges.gov.pt/guias/indcurso.asp?letra_E",
+            "https://www.dges.gov.pt/guias/indcurso_asp?letra=E",
+            "https://www.dges.gov.pt/guias/indcurso.json?letra=E",
+            "https://www.dges.gov.pt/guias/indcurso.asp?letra=%C3%89",
+        ];
+        let paths: Vec<PathBuf> = urls
+            .iter()
+            .map(|url| cassette_path(url).with_extension("json"))
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(
+                paths.iter().filter(|other| *other == path).count(),
+                1,
+                "{} shares {}",
+                urls[i],
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn host_isnt_part_of_the_cassette() {
+        assert_eq!(
+            cassette_path("https://www.dges.gov.pt/guias/detcursopi.asp?codc=9119&code=0807"),
+            cassette_path("http://127.0.0.1:8080/guias/detcursopi.asp?codc=9119&code=0807")
+        );
+    }
+}
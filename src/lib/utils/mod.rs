@@ -1,2 +1,6 @@
 pub mod non_empty_vector;
+pub mod retry_middleware;
+pub mod robots;
+pub mod throttle_middleware;
+pub mod cassette_middleware;
 pub mod charset_middleware;
\ No newline at end of file
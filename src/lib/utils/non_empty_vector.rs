@@ -10,6 +10,17 @@ impl<T> NonEmptyVector<T> {
     pub fn new(elem: T) -> Self {
         NonEmptyVector(vec![elem])
     }
+    /// `None` if `vec` is empty
+    pub fn from_vec(vec: Vec<T>) -> Option<Self> {
+        if vec.is_empty() {
+            None
+        } else {
+            Some(NonEmptyVector(vec))
+        }
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
     pub fn push(&mut self, value: T) {
         self.0.push(value);
     }
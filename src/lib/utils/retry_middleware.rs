@@ -0,0 +1,116 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use chrono::Utc;
+use reqwest::{Request, StatusCode, Url};
+use reqwest_middleware::{Error, Middleware, Next};
+use reqwest_retry::{policies::ExponentialBackoff, RetryPolicy, Retryable};
+use retry_policies::RetryDecision;
+use task_local_extensions::Extensions;
+use tracing::warn;
+
+/// A request that kept failing after every retry
+#[derive(Debug, Clone)]
+pub struct GaveUp {
+    pub url: Url,
+    pub attempts: u32,
+    /// Last status the server answered with, if it answered at all
+    pub status: Option<StatusCode>,
+    pub error: String,
+}
+
+/// Retries transient failures with exponential backoff (and jitter, see [`ExponentialBackoff`]).
+///
+/// Unlike `reqwest_retry::RetryTransientMiddleware`, the retryable status codes are configurable,
+/// and the final failure of each request is handed to `on_give_up`. Errors that aren't worth
+/// retrying are final failures too, but non-retryable statuses (404, ...) aren't.
+pub struct RetryMiddleware<F> {
+    policy: ExponentialBackoff,
+    retryable_statuses: HashSet<StatusCode>,
+    on_give_up: F,
+}
+
+impl<F: Fn(GaveUp) + Send + Sync + 'static> RetryMiddleware<F> {
+    pub fn new(
+        policy: ExponentialBackoff,
+        retryable_statuses: impl IntoIterator<Item = StatusCode>,
+        on_give_up: F,
+    ) -> Self {
+        Self {
+            policy,
+            retryable_statuses: retryable_statuses.into_iter().collect(),
+            on_give_up,
+        }
+    }
+
+    fn should_retry(&self, result: &reqwest_middleware::Result<reqwest::Response>) -> bool {
+        match result {
+            Ok(response) => self.retryable_statuses.contains(&response.status()),
+            // connection resets, timeouts, ...
+            Err(_) => Retryable::from_reqwest_response(result) == Some(Retryable::Transient),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<F: Fn(GaveUp) + Send + Sync + 'static> Middleware for RetryMiddleware<F> {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        let url = req.url().clone();
+        let mut n_past_retries = 0;
+        loop {
+            let request = req.try_clone().ok_or_else(|| {
+                Error::Middleware(anyhow!(
+                    "Request object is not clonable. Are you passing a streaming body?"
+                ))
+            })?;
+
+            let result = next.clone().run(request, extensions).await;
+            if !self.should_retry(&result) {
+                if let Err(ref err) = result {
+                    (self.on_give_up)(GaveUp {
+                        url,
+                        attempts: n_past_retries + 1,
+                        status: None,
+                        error: err.to_string(),
+                    });
+                }
+                return result;
+            }
+
+            match self.policy.should_retry(n_past_retries) {
+                RetryDecision::Retry { execute_after } => {
+                    let duration = (execute_after - Utc::now()).to_std().unwrap_or_default();
+                    warn!(
+                        "Retry attempt #{} for {}. Sleeping {:?} before the next attempt",
+                        n_past_retries + 1,
+                        url,
+                        duration
+                    );
+                    tokio::time::sleep(duration).await;
+                    n_past_retries += 1;
+                }
+                RetryDecision::DoNotRetry => {
+                    let (status, error) = match result {
+                        Ok(ref response) => (
+                            Some(response.status()),
+                            format!("HTTP {}", response.status()),
+                        ),
+                        Err(ref err) => (None, err.to_string()),
+                    };
+                    (self.on_give_up)(GaveUp {
+                        url,
+                        attempts: n_past_retries + 1,
+                        status,
+                        error,
+                    });
+                    return result;
+                }
+            }
+        }
+    }
+}
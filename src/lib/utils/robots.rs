@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+/// Reads the `Crawl-delay` that applies to `user_agent` from a robots.txt file.
+///
+/// A group naming the user agent wins over the `*` group.
+pub fn crawl_delay(robots_txt: &str, user_agent: &str) -> Option<Duration> {
+    let user_agent = user_agent.to_lowercase();
+
+    let mut specific = None;
+    let mut wildcard = None;
+
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_rules = false;
+    for line in robots_txt.lines() {
+        let line = match line.split_once('#') {
+            Some((line, _comment)) => line,
+            None => line,
+        }
+        .trim();
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+
+        match field.as_str() {
+            "user-agent" => {
+                // a user-agent line after some rules starts a new group
+                if in_rules {
+                    group_agents.clear();
+                    in_rules = false;
+                }
+                group_agents.push(value.to_lowercase());
+            }
+            "crawl-delay" => {
+                in_rules = true;
+                // negative, NaN, infinite and overflowing delays are ignored
+                let delay = match value.parse::<f64>().map(Duration::try_from_secs_f64) {
+                    Ok(Ok(delay)) => delay,
+                    _ => continue,
+                };
+                for agent in group_agents.iter() {
+                    if agent == "*" {
+                        wildcard = wildcard.or(Some(delay));
+                    } else if !agent.is_empty() && user_agent.contains(agent.as_str()) {
+                        specific = specific.or(Some(delay));
+                    }
+                }
+            }
+            _ => in_rules = true,
+        }
+    }
+
+    specific.or(wildcard)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_AGENT: &str = "ultron_gauntlet/0.1.0";
+
+    #[test]
+    fn group_naming_the_user_agent_wins() {
+        let robots_txt = "\
+User-agent: *
+Crawl-delay: 10
+
+User-agent: Ultron_Gauntlet
+Crawl-delay: 3
+";
+        assert_eq!(
+            crawl_delay(robots_txt, USER_AGENT),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            crawl_delay(robots_txt, "SomeOtherBot/1.0"),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_wildcard_group() {
+        let robots_txt = "\
+User-agent: googlebot
+Crawl-delay: 1
+
+User-agent: *
+Disallow: /private
+Crawl-delay: 7
+";
+        assert_eq!(
+            crawl_delay(robots_txt, USER_AGENT),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[test]
+    fn groups_can_name_several_user_agents() {
+        let robots_txt = "\
+user-agent: googlebot
+USER-AGENT: ultron_gauntlet
+crawl-DELAY: 2
+Disallow: /
+
+User-agent: googlebot
+Crawl-delay: 30
+";
+        assert_eq!(
+            crawl_delay(robots_txt, USER_AGENT),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn ignores_comments() {
+        let robots_txt = "\
+# Crawl-delay: 1
+User-agent: * # everyone
+Crawl-delay: 4 # be nice
+#Crawl-delay: 2
+";
+        assert_eq!(
+            crawl_delay(robots_txt, USER_AGENT),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_values() {
+        for value in ["", "soon", "-3", "NaN", "inf", "1e300", "5s"] {
+            let robots_txt = format!("User-agent: *\nCrawl-delay: {}\n", value);
+            assert_eq!(crawl_delay(&robots_txt, USER_AGENT), None, "{:?}", value);
+        }
+
+        let robots_txt = "\
+User-agent: *
+Crawl-delay: later
+Crawl-delay: 1.5
+Crawl-delay
+";
+        assert_eq!(
+            crawl_delay(robots_txt, USER_AGENT),
+            Some(Duration::from_millis(1500))
+        );
+    }
+
+    #[test]
+    fn empty_user_agent_matches_nobody() {
+        let robots_txt = "User-agent:\nCrawl-delay: 5\n";
+        assert_eq!(crawl_delay(robots_txt, USER_AGENT), None);
+    }
+
+    #[test]
+    fn no_crawl_delay() {
+        assert_eq!(crawl_delay("", USER_AGENT), None);
+        assert_eq!(
+            crawl_delay("User-agent: *\nDisallow: /\n", USER_AGENT),
+            None
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use reqwest::{header::RETRY_AFTER, Request, StatusCode};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+use tokio::{sync::Mutex, time::Instant};
+use tracing::{info, warn};
+
+/// How much the delay grows when the server is struggling
+const BACKOFF_FACTOR: f64 = 2.0;
+/// How much the delay shrinks after each healthy response
+const RECOVERY_FACTOR: f64 = 0.9;
+/// A response this many times slower than the average means the server is getting busy
+const SLOW_RESPONSE_FACTOR: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleConfig {
+    /// Delay the crawl starts with
+    pub initial: Duration,
+    /// The delay never goes below this, no matter how healthy the server looks
+    pub floor: Duration,
+    /// The delay never goes above this, no matter how much the server struggles
+    pub ceiling: Duration,
+}
+
+struct ThrottleState {
+    delay: Duration,
+    next_request: Instant,
+    average_response_time: Option<Duration>,
+}
+
+/// Spaces requests out, adapting the delay to how the server is coping.
+///
+/// 429 and 503 responses (and a `Retry-After` header), connection errors, timeouts or response
+/// times well above the average make the delay grow, every healthy response makes it shrink back towards the floor.
+/// Requests go out one at a time.
+pub struct ThrottleMiddleware {
+    config: ThrottleConfig,
+    state: Mutex<ThrottleState>,
+}
+
+impl ThrottleMiddleware {
+    pub fn new(config: ThrottleConfig) -> Self {
+        let initial = config
+            .initial
+            .clamp(config.floor, config.ceiling.max(config.floor));
+        Self {
+            config,
+            state: Mutex::new(ThrottleState {
+                delay: initial,
+                next_request: Instant::now(),
+                average_response_time: None,
+            }),
+        }
+    }
+
+    fn adapt(
+        &self,
+        state: &mut ThrottleState,
+        result: &reqwest_middleware::Result<reqwest::Response>,
+        response_time: Duration,
+    ) {
+        let old_delay = state.delay;
+        let overloaded = match result {
+            Ok(response) => {
+                let status = response.status();
+                if status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::SERVICE_UNAVAILABLE
+                {
+                    if let Some(retry_after) = retry_after(response) {
+                        state.delay = state.delay.max(retry_after);
+                    }
+                    true
+                } else {
+                    false
+                }
+            }
+            // not answering at all is the worst way to struggle
+            Err(reqwest_middleware::Error::Reqwest(err)) => err.is_connect() || err.is_timeout(),
+            Err(_) => false,
+        };
+        let slow = match state.average_response_time {
+            Some(average) => response_time > average.mul_f64(SLOW_RESPONSE_FACTOR),
+            None => false,
+        };
+
+        state.delay = if overloaded || slow {
+            state.delay.mul_f64(BACKOFF_FACTOR)
+        } else {
+            state.delay.mul_f64(RECOVERY_FACTOR)
+        }
+        .clamp(
+            self.config.floor,
+            self.config.ceiling.max(self.config.floor),
+        );
+
+        state.average_response_time = Some(match state.average_response_time {
+            // exponential moving average, so a few slow responses don't stick forever
+            Some(average) => average.mul_f64(0.8) + response_time.mul_f64(0.2),
+            None => response_time,
+        });
+
+        if state.delay > old_delay {
+            warn!(
+                "Server is struggling, slowing down to one request every {:?}",
+                state.delay
+            );
+            state.next_request = state.next_request.max(Instant::now() + state.delay);
+        } else if state.delay < old_delay && state.delay == self.config.floor {
+            info!(
+                "Server is healthy, back to one request every {:?}",
+                state.delay
+            );
+        }
+    }
+}
+
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let seconds = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+#[async_trait::async_trait]
+impl Middleware for ThrottleMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<reqwest::Response> {
+        {
+            // holding the lock while sleeping keeps requests in line
+            let mut state = self.state.lock().await;
+            tokio::time::sleep_until(state.next_request).await;
+            state.next_request = Instant::now() + state.delay;
+        }
+
+        let started = Instant::now();
+        let result = next.run(req, extensions).await;
+        let response_time = started.elapsed();
+
+        let mut state = self.state.lock().await;
+        self.adapt(&mut state, &result, response_time);
+
+        result
+    }
+}
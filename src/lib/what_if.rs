@@ -0,0 +1,171 @@
+//! What if the candidate took one more exam: which exam they haven't taken opens up the most
+//! courses they can't apply to today.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+
+use super::course_url::CourseUrl;
+use super::db::{get_course_areas, get_exam_names, get_institution_addresses};
+use super::eligibility::{course_eligibility, Eligibility};
+use super::exams::ExamCode;
+use super::filters::CrawlFilter;
+use super::profile::CandidateProfile;
+
+/// Which courses count. Everything, if empty
+#[derive(Debug, Default)]
+pub struct WhatIfFilter {
+    /// CNAEF areas of any level, everything under them (`4`, `48` and `481` all match `481`)
+    pub cnaef_areas: Vec<String>,
+    /// Institution code patterns, `*` matches anything (`03*`)
+    pub institutions: Vec<String>,
+    /// Towns, as written after the postal code in the institution's address (`Lisboa`)
+    pub towns: Vec<String>,
+}
+
+/// An exam the candidate hasn't taken and the courses it would let them apply to
+pub struct UnlockedCourses {
+    pub exam: ExamCode,
+    pub name: Option<String>,
+    pub(crate) courses: BTreeSet<CourseUrl>,
+}
+
+/// Every exam in the catalogue the candidate hasn't taken, the ones that unlock the most courses
+/// first. Assumes the new exam would be passed with the course's minimum grade
+pub fn what_if(
+    conn: &mut SqliteConnection,
+    guide_year: i32,
+    profile: &CandidateProfile,
+    filter: &WhatIfFilter,
+) -> Result<Vec<UnlockedCourses>> {
+    let crawl_filter = CrawlFilter {
+        institutions: filter.institutions.clone(),
+        cnaef_areas: filter.cnaef_areas.clone(),
+        ..Default::default()
+    };
+    let areas: HashMap<CourseUrl, String> = get_course_areas(conn, guide_year)?
+        .into_iter()
+        .map(|row| {
+            (
+                CourseUrl::new(&row.institution, &row.course),
+                row.cnaef_area,
+            )
+        })
+        .collect();
+    let towns: HashMap<String, String> = get_institution_addresses(conn, guide_year)?
+        .into_iter()
+        .filter_map(|(code, address)| Some((code, town(&address?)?)))
+        .collect();
+
+    let allowed = |course: &CourseUrl| {
+        let institution = course.institution_code();
+        crawl_filter.allows_institution(institution)
+            && crawl_filter.allows_cnaef_area(areas.get(course).map(String::as_str))
+            && (filter.towns.is_empty()
+                || towns.get(institution).map_or(false, |town| {
+                    filter
+                        .towns
+                        .iter()
+                        .any(|wanted| wanted.eq_ignore_ascii_case(town))
+                }))
+    };
+
+    // the ones out for their application grade already have the exams, and nothing is known of
+    // what the ones without requirements need
+    let out_of_reach: Vec<_> = course_eligibility(conn, guide_year, profile)?
+        .into_iter()
+        .filter(|course| {
+            !course.eligibility.is_eligible()
+                && !matches!(
+                    course.eligibility,
+                    Eligibility::NotEligible {
+                        application_grade: Some(_),
+                        ..
+                    } | Eligibility::Unknown
+                )
+                && allowed(&course.course)
+        })
+        .collect();
+
+    let mut unlocked: Vec<UnlockedCourses> = get_exam_names(conn)?
+        .into_iter()
+        .map(|(code, name)| (ExamCode::from(code.as_str()), name))
+        .filter(|(exam, _)| !profile.took(exam))
+        .map(|(exam, name)| {
+            let courses = out_of_reach
+                .iter()
+                .filter(|course| {
+                    let mut passed = profile.passed(course.minimum_grades.exams);
+                    passed.insert(exam.clone());
+                    course
+                        .requirement
+                        .as_ref()
+                        .map_or(false, |requirement| requirement.satisfied_by(&passed))
+                })
+                .map(|course| course.course.clone())
+                .collect();
+            UnlockedCourses {
+                exam,
+                name,
+                courses,
+            }
+        })
+        .filter(|unlocked| !unlocked.courses.is_empty())
+        .collect();
+
+    unlocked.sort_by(|a, b| {
+        b.courses
+            .len()
+            .cmp(&a.courses.len())
+            .then_with(|| a.exam.cmp(&b.exam))
+    });
+    Ok(unlocked)
+}
+
+/// The town after the postal code, `Lisboa` in `... 1649-004 Lisboa`. There's no region anywhere
+/// on the pages, this is the closest thing
+fn town(address: &str) -> Option<String> {
+    let words: Vec<&str> = address.split_whitespace().collect();
+    let postal_code = words.iter().rposition(|word| is_postal_code(word))?;
+    let town = words[postal_code + 1..].join(" ");
+    if town.is_empty() {
+        None
+    } else {
+        Some(town)
+    }
+}
+
+/// `1649-004`
+fn is_postal_code(word: &str) -> bool {
+    match word.split_once('-') {
+        Some((zone, local)) => {
+            zone.len() == 4
+                && local.len() == 3
+                && zone
+                    .chars()
+                    .chain(local.chars())
+                    .all(|c| c.is_ascii_digit())
+        }
+        None => false,
+    }
+}
+
+/// One exam per line, with the courses it unlocks if `list` is set
+pub fn what_if_report(unlocked: &[UnlockedCourses], list: bool) -> String {
+    let mut report = String::new();
+    for exam in unlocked {
+        report.push_str(&format!(
+            "{:>4}  {}  {}\n",
+            exam.courses.len(),
+            exam.exam,
+            exam.name.as_deref().unwrap_or("-")
+        ));
+        if list {
+            for course in exam.courses.iter() {
+                report.push_str(&format!("        {}\n", course));
+            }
+        }
+    }
+    report
+}
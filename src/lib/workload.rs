@@ -0,0 +1,246 @@
+//! How long a course takes and how many ECTS it's worth, normalized so courses can be compared,
+//! and checked against each other. A full time academic year is 60 ECTS.
+
+use anyhow::Result;
+use diesel::SqliteConnection;
+use thiserror::Error;
+
+use super::characteristics::{Duration, Ects};
+use super::db::get_anomalies;
+
+pub(crate) const ECTS_PER_YEAR: f64 = 60.0;
+
+/// How far from `ECTS_PER_YEAR` a course can be before it looks wrong
+const ECTS_PER_YEAR_TOLERANCE: f64 = 3.0;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub(crate) enum Anomaly {
+    #[error("no duration")]
+    MissingDuration,
+    #[error("duration has no unit")]
+    MissingUnit,
+    #[error("duration unit \"{0}\" isn't a known length of time")]
+    UnknownUnit(String),
+    #[error("no ECTS")]
+    MissingEcts,
+    #[error(
+        "{ects} ECTS in {months} months is {per_year:.1} a year, expected {}",
+        ECTS_PER_YEAR
+    )]
+    UnusualEctsPerYear {
+        ects: u16,
+        months: u16,
+        per_year: f64,
+    },
+}
+
+impl Anomaly {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Anomaly::MissingDuration => "missing_duration",
+            Anomaly::MissingUnit => "missing_unit",
+            Anomaly::UnknownUnit(_) => "unknown_unit",
+            Anomaly::MissingEcts => "missing_ects",
+            Anomaly::UnusualEctsPerYear { .. } => "unusual_ects_per_year",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct Workload {
+    pub(crate) months: Option<u16>,
+    pub(crate) ects: Option<u16>,
+}
+
+impl Workload {
+    pub(crate) fn new(duration: &Duration, ects: Option<Ects>) -> Self {
+        Workload {
+            months: duration.months(),
+            ects: ects.map(u16::from),
+        }
+    }
+
+    pub(crate) fn years(&self) -> Option<f64> {
+        Some(f64::from(self.months?) / 12.0)
+    }
+
+    pub(crate) fn ects_per_year(&self) -> Option<f64> {
+        let years = self.years()?;
+        if years > 0.0 {
+            Some(f64::from(self.ects?) / years)
+        } else {
+            None
+        }
+    }
+}
+
+/// Everything that looks off about the duration and ECTS of a course
+pub(crate) fn check_workload(duration: &Duration, ects: Option<Ects>) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    match (&duration.ammount, &duration.unit) {
+        (None, _) => anomalies.push(Anomaly::MissingDuration),
+        (Some(_), None) => anomalies.push(Anomaly::MissingUnit),
+        (Some(_), Some(unit)) if unit.months().is_none() => {
+            anomalies.push(Anomaly::UnknownUnit(unit.to_string()))
+        }
+        _ => {}
+    }
+    if ects.is_none() {
+        anomalies.push(Anomaly::MissingEcts);
+    }
+
+    let workload = Workload::new(duration, ects);
+    if let (Some(ects), Some(months), Some(per_year)) =
+        (workload.ects, workload.months, workload.ects_per_year())
+    {
+        if (per_year - ECTS_PER_YEAR).abs() > ECTS_PER_YEAR_TOLERANCE {
+            anomalies.push(Anomaly::UnusualEctsPerYear {
+                ects,
+                months,
+                per_year,
+            });
+        }
+    }
+    anomalies
+}
+
+/// One anomaly per line, everything recorded or only what a given run recorded
+pub fn anomaly_report(conn: &mut SqliteConnection, run: Option<i32>) -> Result<String> {
+    let mut report = String::new();
+    for anomaly in get_anomalies(conn, run)? {
+        report.push_str(&format!(
+            "{:>4}  {}/{}  {:<21}  {}\n",
+            anomaly.run, anomaly.institution, anomaly.course, anomaly.kind, anomaly.detail
+        ));
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::characteristics::Unit;
+
+    fn duration(ammount: Option<u8>, unit: Option<Unit>) -> Duration {
+        Duration {
+            ammount: ammount.map(Into::into),
+            unit,
+        }
+    }
+
+    fn ects(ects: u16) -> Option<Ects> {
+        Some(Ects::from(ects))
+    }
+
+    fn semesters(ammount: u8) -> Duration {
+        duration(Some(ammount), Some(Unit::Semesters))
+    }
+
+    #[test]
+    fn months_per_unit() {
+        assert_eq!(semesters(6).months(), Some(36));
+        assert_eq!(duration(Some(3), Some(Unit::Years)).months(), Some(36));
+        assert_eq!(duration(Some(4), Some(Unit::Trimesters)).months(), Some(12));
+        assert_eq!(
+            duration(Some(3), Some(Unit::Other("Meses".to_string()))).months(),
+            None
+        );
+        assert_eq!(duration(Some(3), None).months(), None);
+        assert_eq!(duration(None, Some(Unit::Years)).months(), None);
+    }
+
+    #[test]
+    fn ects_per_year() {
+        let workload = Workload::new(&semesters(6), ects(180));
+        assert_eq!(workload.ects_per_year(), Some(60.0));
+        let workload = Workload::new(&semesters(10), ects(300));
+        assert_eq!(workload.ects_per_year(), Some(60.0));
+        // nothing to divide by
+        let workload = Workload::new(&semesters(0), ects(180));
+        assert_eq!(workload.ects_per_year(), None);
+        let workload = Workload::new(&semesters(6), None);
+        assert_eq!(workload.ects_per_year(), None);
+    }
+
+    #[test]
+    fn usual_workload() {
+        assert_eq!(check_workload(&semesters(6), ects(180)), vec![]);
+    }
+
+    #[test]
+    fn six_semesters_with_240_ects() {
+        assert_eq!(
+            check_workload(&semesters(6), ects(240)),
+            vec![Anomaly::UnusualEctsPerYear {
+                ects: 240,
+                months: 36,
+                per_year: 80.0
+            }]
+        );
+    }
+
+    #[test]
+    fn tolerance_boundary() {
+        // 63 and 57 a year
+        assert_eq!(check_workload(&semesters(6), ects(189)), vec![]);
+        assert_eq!(check_workload(&semesters(6), ects(171)), vec![]);
+        // 63.3 and 56.7
+        assert_eq!(
+            check_workload(&semesters(6), ects(190))
+                .iter()
+                .map(Anomaly::kind)
+                .collect::<Vec<_>>(),
+            vec!["unusual_ects_per_year"]
+        );
+        assert_eq!(
+            check_workload(&semesters(6), ects(170))
+                .iter()
+                .map(Anomaly::kind)
+                .collect::<Vec<_>>(),
+            vec!["unusual_ects_per_year"]
+        );
+    }
+
+    #[test]
+    fn missing_duration() {
+        assert_eq!(
+            check_workload(&duration(None, Some(Unit::Semesters)), ects(180)),
+            vec![Anomaly::MissingDuration]
+        );
+    }
+
+    #[test]
+    fn missing_unit() {
+        assert_eq!(
+            check_workload(&duration(Some(6), None), ects(180)),
+            vec![Anomaly::MissingUnit]
+        );
+    }
+
+    #[test]
+    fn unknown_unit() {
+        assert_eq!(
+            check_workload(
+                &duration(Some(6), Some(Unit::Other("Meses".to_string()))),
+                ects(180)
+            ),
+            vec![Anomaly::UnknownUnit("Meses".to_string())]
+        );
+    }
+
+    #[test]
+    fn missing_ects() {
+        assert_eq!(
+            check_workload(&semesters(6), None),
+            vec![Anomaly::MissingEcts]
+        );
+    }
+
+    #[test]
+    fn missing_everything() {
+        assert_eq!(
+            check_workload(&Duration::default(), None),
+            vec![Anomaly::MissingDuration, Anomaly::MissingEcts]
+        );
+    }
+}
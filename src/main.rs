@@ -5,7 +5,9 @@ pub mod lib;
 use std::{
     ffi::OsStr,
     fs::File,
+    net::SocketAddr,
     path::{Path, PathBuf},
+    time::Duration,
 };
 
 use std::error::Error;
@@ -13,10 +15,37 @@ use std::error::Error;
 use serde::Deserialize;
 
 use anyhow::Result;
-use clap::Parser;
-use lib::{all_courses, handle_results, select_courses};
+use clap::{Parser, Subcommand};
+use lib::{
+    all_courses,
+    application::{application_grade_table, application_grades},
+    cnaef::{area_report, area_tree},
+    config::{
+        ClientConfig, PolitenessConfig, RetryConfig, SiteConfig, DEFAULT_BASE_URL,
+        DEFAULT_COURSE_PATH, DEFAULT_INDEX_PATH,
+    },
+    coverage::Coverage,
+    db::{establish_connection, establish_fresh_connection, get_guide_years},
+    eligibility::course_eligibility,
+    exams::corpus::check_corpus,
+    filters::CrawlFilter,
+    fingerprint::{DriftMode, LayoutCheck, LayoutFingerprint},
+    guides::{compare_guides, resolve_guide_year},
+    handle_results,
+    mock_server::{self, FaultRule, MockServerConfig},
+    planner::plan_application,
+    profile::{CandidateProfile, Preference},
+    retry_failed, select_courses,
+    trends::{course_trends, trend_summary_table, trend_table},
+    unrecognized_content_report,
+    utils::cassette_middleware::CassetteMode,
+    what_if::{what_if, what_if_report, WhatIfFilter},
+    workload::anomaly_report,
+};
+use reqwest::StatusCode;
+use url::Url;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 struct Record {
     institution_code: String,
     course_code: String,
@@ -25,9 +54,380 @@ struct Record {
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// Sets a custom source file
     #[clap(short, long, value_name = "FILE", validator = csv_file_exists)]
     source: Option<PathBuf>,
+
+    /// Deletes what earlier runs saved about courses before crawling, instead of adding to it. Runs, what was saved per run and failed pages stay
+    #[clap(long)]
+    fresh: bool,
+
+    /// Where the coverage report of the run is written
+    #[clap(long, value_name = "FILE", default_value = "coverage.md")]
+    coverage_report: PathBuf,
+
+    #[clap(flatten)]
+    layout: LayoutArgs,
+
+    #[clap(flatten)]
+    site: SiteArgs,
+
+    #[clap(flatten)]
+    client: ClientArgs,
+
+    #[clap(flatten)]
+    filter: FilterArgs,
+}
+
+#[derive(clap::Args)]
+struct SiteArgs {
+    /// Base url every page is resolved against. Point it to a mirror or a local mock server
+    #[clap(long, value_name = "URL", default_value = DEFAULT_BASE_URL)]
+    base_url: Url,
+
+    /// Path of the letter index page, relative to the base url
+    #[clap(long, value_name = "PATH", default_value = DEFAULT_INDEX_PATH)]
+    index_path: String,
+
+    /// Path of the course detail page, relative to the base url
+    #[clap(long, value_name = "PATH", default_value = DEFAULT_COURSE_PATH)]
+    course_path: String,
+
+    /// Domain the crawler is allowed to visit. Defaults to the host of the base url
+    #[clap(long = "allow-domain", value_name = "DOMAIN")]
+    allowed_domains: Vec<String>,
+
+    /// Year of the guide the site serves, for pages that don't say. Point the base url to an older
+    /// guide to crawl it
+    #[clap(long, value_name = "YEAR")]
+    guide_year: Option<u16>,
+}
+
+#[derive(clap::Args)]
+struct ClientArgs {
+    /// Saves every request/response pair into a cassette directory
+    #[clap(long, value_name = "DIR", conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answers every request from a cassette directory, failing on unknown requests
+    #[clap(long, value_name = "DIR")]
+    replay: Option<PathBuf>,
+
+    /// How many times a failed request is retried. 0 disables retrying
+    #[clap(long, value_name = "N", default_value_t = 3)]
+    max_retries: u32,
+
+    /// Minimum wait between retries, in milliseconds
+    #[clap(long, value_name = "MS", default_value_t = 1000)]
+    retry_min_delay: u64,
+
+    /// Maximum wait between retries, in milliseconds
+    #[clap(long, value_name = "MS", default_value_t = 60000)]
+    retry_max_delay: u64,
+
+    /// How fast the wait between retries grows
+    #[clap(long, value_name = "N", default_value_t = 2)]
+    backoff_exponent: u32,
+
+    /// Status code worth retrying. Defaults to 408, 429, 500, 502, 503 and 504
+    #[clap(long = "retry-status", value_name = "STATUS")]
+    retry_statuses: Vec<StatusCode>,
+
+    /// Delay between requests when robots.txt doesn't set a Crawl-delay, in milliseconds
+    #[clap(long, value_name = "MS", default_value_t = 10000)]
+    delay: u64,
+
+    /// The delay shrinks down to this while the server is healthy (never below Crawl-delay), in milliseconds. Lower it to crawl faster
+    #[clap(long, value_name = "MS", default_value_t = 10000)]
+    min_delay: u64,
+
+    /// The delay grows up to this while the server is struggling, in milliseconds
+    #[clap(long, value_name = "MS", default_value_t = 120000)]
+    max_delay: u64,
+
+    /// Ignores the Crawl-delay from robots.txt
+    #[clap(long)]
+    ignore_crawl_delay: bool,
+}
+
+impl From<ClientArgs> for ClientConfig {
+    fn from(args: ClientArgs) -> Self {
+        let cassette = match (args.record, args.replay) {
+            (Some(directory), _) => Some((CassetteMode::Record, directory)),
+            (None, Some(directory)) => Some((CassetteMode::Replay, directory)),
+            (None, None) => None,
+        };
+        let mut retry = RetryConfig {
+            max_retries: args.max_retries,
+            min_interval: Duration::from_millis(args.retry_min_delay),
+            max_interval: Duration::from_millis(args.retry_max_delay),
+            backoff_exponent: args.backoff_exponent,
+            ..RetryConfig::default()
+        };
+        if !args.retry_statuses.is_empty() {
+            retry.retryable_statuses = args.retry_statuses;
+        }
+        let politeness = PolitenessConfig {
+            default_delay: Duration::from_millis(args.delay),
+            min_delay: Duration::from_millis(args.min_delay),
+            max_delay: Duration::from_millis(args.max_delay),
+            respect_crawl_delay: !args.ignore_crawl_delay,
+        };
+        ClientConfig {
+            cassette,
+            retry,
+            politeness,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct FilterArgs {
+    /// Only crawls these letter index pages
+    #[clap(long, value_name = "LETTERS", use_value_delimiter = true)]
+    letters: Vec<String>,
+
+    /// Only crawls institutions whose code matches, `*` matches anything (e.g. 03*)
+    #[clap(long = "institution", value_name = "PATTERN")]
+    institutions: Vec<String>,
+
+    /// Only saves courses under this CNAEF area, of any level (e.g. 4, 48 or 481)
+    #[clap(long = "cnaef-area", value_name = "CODE")]
+    cnaef_areas: Vec<String>,
+
+    /// Stops after this many courses
+    #[clap(long, value_name = "N")]
+    max_courses: Option<usize>,
+
+    /// Crawls a random subset of this many courses
+    #[clap(long, value_name = "N")]
+    sample: Option<usize>,
+}
+
+impl From<FilterArgs> for CrawlFilter {
+    fn from(args: FilterArgs) -> Self {
+        CrawlFilter {
+            letters: args
+                .letters
+                .iter()
+                .map(|letter| letter.trim().to_uppercase())
+                .collect(),
+            institutions: args.institutions,
+            cnaef_areas: args.cnaef_areas,
+            max_courses: args.max_courses,
+            sample: args.sample,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+struct LayoutArgs {
+    /// Page structure the run is compared against. Create it with --update-layout-baseline
+    #[clap(long, value_name = "FILE", default_value = "layout-baseline.json")]
+    layout_baseline: PathBuf,
+
+    /// What to do when the pages drift from the baseline: warn or fail
+    #[clap(long, value_name = "MODE", default_value = "warn")]
+    layout_drift: DriftMode,
+
+    /// Saves this run's page structure as the new baseline
+    #[clap(long)]
+    update_layout_baseline: bool,
+}
+
+impl From<LayoutArgs> for LayoutCheck {
+    fn from(args: LayoutArgs) -> Self {
+        LayoutCheck {
+            baseline: args.layout_baseline,
+            mode: args.layout_drift,
+            update: args.update_layout_baseline,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serves a directory of fixture pages with the same url shapes as DGES
+    MockServer(MockServerArgs),
+    /// Crawls again only the pages that failed in previous runs
+    RetryFailed,
+    /// Lists the headers and fields the scraper doesn't know, most common first
+    UnrecognizedContent {
+        /// Only what this run found
+        #[clap(long, value_name = "ID")]
+        run: Option<i32>,
+    },
+    /// Checks the exams parser against a corpus of "Provas de Ingresso" fragments
+    ExamCorpus {
+        #[clap(value_name = "DIR", default_value = "fixtures/exams")]
+        directory: PathBuf,
+    },
+    /// Lists the courses a candidate can apply to, going by their entrance exams
+    Eligibility {
+        /// The candidate's profile, a .toml or .json file
+        #[clap(long, value_name = "FILE")]
+        profile: PathBuf,
+
+        /// Also lists the courses they can't apply to, and why
+        #[clap(long)]
+        all: bool,
+
+        /// Year of the guide to go by. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        guide_year: Option<u16>,
+    },
+    /// Ranks the courses a candidate can apply to by their application grade against past cutoffs
+    ApplicationGrades {
+        /// The candidate's profile, a .toml or .json file
+        #[clap(long, value_name = "FILE")]
+        profile: PathBuf,
+
+        /// Year of the guide to go by. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        guide_year: Option<u16>,
+    },
+    /// Checks a candidate profile against the exams in the database
+    CheckProfile {
+        #[clap(value_name = "FILE")]
+        profile: PathBuf,
+    },
+    /// Checks an application list, option by option, against past cutoffs
+    Plan {
+        /// The candidate's profile, a .toml or .json file
+        #[clap(long, value_name = "FILE")]
+        profile: PathBuf,
+
+        /// An option, in order (e.g. 0807/9119). Defaults to the preferences in the profile
+        #[clap(long = "option", value_name = "INSTITUTION/COURSE")]
+        options: Vec<Preference>,
+
+        /// Year of the guide to go by. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        guide_year: Option<u16>,
+    },
+    /// How the last placed grade, vacancies and demand of courses changed over the years
+    Trends {
+        /// Only this course (e.g. 0807/9119). Every course with statistics, if not given
+        #[clap(long = "course", value_name = "INSTITUTION/COURSE")]
+        courses: Vec<Preference>,
+
+        /// Application phase, 1 to 3
+        #[clap(long, default_value = "1")]
+        phase: u8,
+
+        /// Shows every year instead of a line per course
+        #[clap(long)]
+        years: bool,
+
+        /// Prints JSON instead of a table
+        #[clap(long)]
+        json: bool,
+    },
+    /// Ranks the exams a candidate hasn't taken by how many more courses they'd let them apply to
+    WhatIf {
+        /// The candidate's profile, a .toml or .json file
+        #[clap(long, value_name = "FILE")]
+        profile: PathBuf,
+
+        /// Only counts courses under this CNAEF area, of any level (e.g. 4, 48 or 481)
+        #[clap(long = "cnaef-area", value_name = "CODE")]
+        cnaef_areas: Vec<String>,
+
+        /// Only counts institutions whose code matches, `*` matches anything (e.g. 03*)
+        #[clap(long = "institution", value_name = "PATTERN")]
+        institutions: Vec<String>,
+
+        /// Only counts institutions in this town, as written in their address (e.g. Lisboa)
+        #[clap(long = "town", value_name = "TOWN")]
+        towns: Vec<String>,
+
+        /// Lists the courses each exam unlocks
+        #[clap(long)]
+        list: bool,
+
+        /// Year of the guide to go by. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        guide_year: Option<u16>,
+    },
+    /// Lists the courses one guide has and another doesn't, and the exam requirements that changed
+    CompareGuides {
+        /// The older guide. The one before `--to`, if not given
+        #[clap(long, value_name = "YEAR")]
+        from: Option<u16>,
+
+        /// The newer guide. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        to: Option<u16>,
+    },
+    /// Lists the CNAEF areas, broad groups first, with how many courses are under each one
+    Areas {
+        /// Only this area and the ones under it, of any level (e.g. 4, 48 or 481)
+        #[clap(long, value_name = "CODE")]
+        under: Option<String>,
+
+        /// Lists the courses of each detailed field
+        #[clap(long)]
+        list: bool,
+
+        /// Prints the whole tree as JSON, areas with no courses included
+        #[clap(long)]
+        json: bool,
+
+        /// Year of the guide to go by. The most recent one crawled, if not given
+        #[clap(long, value_name = "YEAR")]
+        guide_year: Option<u16>,
+    },
+    /// Lists courses whose duration and ECTS don't add up, or are missing
+    Anomalies {
+        /// Only what this run found. Every run, if not given
+        #[clap(long, value_name = "ID")]
+        run: Option<i32>,
+    },
+}
+
+#[derive(clap::Args)]
+struct MockServerArgs {
+    /// Address to listen on
+    #[clap(long, value_name = "ADDRESS", default_value = "127.0.0.1:8080")]
+    address: SocketAddr,
+
+    /// Directory with the fixture pages
+    #[clap(long, value_name = "DIR", default_value = "fixtures/dges")]
+    fixtures: PathBuf,
+
+    /// Injects a fault: KIND[@PROBABILITY][:PATTERN], with KIND one of 404, 500, error or slow
+    #[clap(long = "inject", value_name = "RULE")]
+    faults: Vec<FaultRule>,
+
+    /// How long slow responses take, in milliseconds
+    #[clap(long, value_name = "MS", default_value = "5000")]
+    slow_delay: u64,
+}
+
+impl From<MockServerArgs> for MockServerConfig {
+    fn from(args: MockServerArgs) -> Self {
+        MockServerConfig {
+            address: args.address,
+            fixtures: args.fixtures,
+            faults: args.faults,
+            slow_delay: Duration::from_millis(args.slow_delay),
+        }
+    }
+}
+
+impl From<SiteArgs> for SiteConfig {
+    fn from(args: SiteArgs) -> Self {
+        SiteConfig {
+            base_url: args.base_url,
+            index_path: args.index_path,
+            course_path: args.course_path,
+            allowed_domains: args.allowed_domains,
+            guide_year: args.guide_year,
+        }
+    }
 }
 
 fn csv_file_exists(value: &str) -> Result<(), String> {
@@ -49,17 +449,207 @@ fn csv_file_exists(value: &str) -> Result<(), String> {
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    let mut collector = if let Some(source) = args.source {
-        select_courses(read_courses(source).unwrap().into_iter()).await
-    } else {
-        all_courses().await
-    };
+    tracing_subscriber::fmt::init();
 
-    handle_results(&mut collector).await;
+    match args.command {
+        Some(Command::MockServer(mock_server_args)) => {
+            mock_server::serve(mock_server_args.into()).await?;
+        }
+        Some(Command::RetryFailed) => {
+            let mut conn = establish_connection();
+            let mut collector = retry_failed(
+                args.site.into(),
+                args.client.into(),
+                args.filter.into(),
+                &mut conn,
+            )
+            .await?;
+            let coverage = handle_results(&mut collector, &mut conn).await;
+            coverage.write(&args.coverage_report)?;
+            LayoutCheck::from(args.layout).check(&collector.layout(), collector.is_partial())?;
+        }
+        Some(Command::UnrecognizedContent { run }) => {
+            let mut conn = establish_connection();
+            print!("{}", unrecognized_content_report(&mut conn, run)?);
+        }
+        Some(Command::ExamCorpus { directory }) => {
+            let cases = check_corpus(&directory)?;
+            let failures = cases.iter().filter(|case| !case.passed()).count();
+            for case in &cases {
+                if case.passed() {
+                    println!("ok      {}", case.name);
+                } else {
+                    println!("FAILED  {}", case.name);
+                    println!("    expected: {}", case.expected);
+                    println!("    actual:   {}", case.actual);
+                }
+            }
+            println!("{} cases, {} failed", cases.len(), failures);
+            if failures > 0 {
+                anyhow::bail!("{} exam corpus cases failed", failures);
+            }
+        }
+        Some(Command::Eligibility {
+            profile,
+            all,
+            guide_year,
+        }) => {
+            let mut conn = establish_connection();
+            let guide_year = resolve_guide_year(&mut conn, guide_year)?;
+            let profile = CandidateProfile::load(&profile, &mut conn)?;
+            for course in course_eligibility(&mut conn, guide_year, &profile)? {
+                if all || course.eligibility.is_eligible() {
+                    println!("{}", course);
+                }
+            }
+        }
+        Some(Command::ApplicationGrades {
+            profile,
+            guide_year,
+        }) => {
+            let mut conn = establish_connection();
+            let guide_year = resolve_guide_year(&mut conn, guide_year)?;
+            let profile = CandidateProfile::load(&profile, &mut conn)?;
+            let grades = application_grades(&mut conn, guide_year, &profile)?;
+            print!("{}", application_grade_table(&grades));
+        }
+        Some(Command::CheckProfile { profile }) => {
+            let mut conn = establish_connection();
+            let profile = CandidateProfile::load(&profile, &mut conn)?;
+            println!(
+                "ok: {} exams, {} preferences",
+                profile.exams.len(),
+                profile.preferences.len()
+            );
+        }
+        Some(Command::Plan {
+            profile,
+            options,
+            guide_year,
+        }) => {
+            let mut conn = establish_connection();
+            let guide_year = resolve_guide_year(&mut conn, guide_year)?;
+            let profile = CandidateProfile::load(&profile, &mut conn)?;
+            let options = if options.is_empty() {
+                &profile.preferences
+            } else {
+                &options
+            };
+            let plan = plan_application(&mut conn, guide_year, &profile, options)?;
+            print!("{}", plan);
+        }
+        Some(Command::Trends {
+            courses,
+            phase,
+            years,
+            json,
+        }) => {
+            let mut conn = establish_connection();
+            let trends = course_trends(&mut conn, phase, &courses)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&trends)?);
+            } else if years {
+                print!("{}", trend_table(&trends));
+            } else {
+                print!("{}", trend_summary_table(&trends));
+            }
+        }
+        Some(Command::WhatIf {
+            profile,
+            cnaef_areas,
+            institutions,
+            towns,
+            list,
+            guide_year,
+        }) => {
+            let mut conn = establish_connection();
+            let guide_year = resolve_guide_year(&mut conn, guide_year)?;
+            let profile = CandidateProfile::load(&profile, &mut conn)?;
+            let filter = WhatIfFilter {
+                cnaef_areas,
+                institutions,
+                towns,
+            };
+            let unlocked = what_if(&mut conn, guide_year, &profile, &filter)?;
+            print!("{}", what_if_report(&unlocked, list));
+        }
+        Some(Command::CompareGuides { from, to }) => {
+            let mut conn = establish_connection();
+            let to = resolve_guide_year(&mut conn, to)?;
+            let years = get_guide_years(&mut conn)?;
+            let from = match from {
+                Some(from) => i32::from(from),
+                None => match years.into_iter().find(|year| *year < to) {
+                    Some(year) => year,
+                    None => anyhow::bail!("No guide crawled before {}", to),
+                },
+            };
+            print!("{}", compare_guides(&mut conn, from, to)?);
+        }
+        Some(Command::Areas {
+            under,
+            list,
+            json,
+            guide_year,
+        }) => {
+            let mut conn = establish_connection();
+            let guide_year = resolve_guide_year(&mut conn, guide_year)?;
+            let tree = area_tree(&mut conn, guide_year, under.as_deref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&tree)?);
+            } else {
+                print!("{}", area_report(&tree, list));
+            }
+        }
+        Some(Command::Anomalies { run }) => {
+            let mut conn = establish_connection();
+            print!("{}", anomaly_report(&mut conn, run)?);
+        }
+        None => {
+            let (coverage, layout, partial) = crawl(
+                args.source,
+                args.site.into(),
+                args.client.into(),
+                args.filter.into(),
+                args.fresh,
+            )
+            .await;
+            coverage.write(&args.coverage_report)?;
+            LayoutCheck::from(args.layout).check(&layout, partial)?;
+        }
+    }
 
     Ok(())
 }
 
+async fn crawl(
+    source: Option<PathBuf>,
+    site: SiteConfig,
+    client: ClientConfig,
+    filter: CrawlFilter,
+    fresh: bool,
+) -> (Coverage, LayoutFingerprint, bool) {
+    let mut collector = if let Some(source) = source {
+        select_courses(
+            site,
+            client,
+            filter,
+            read_courses(source).unwrap().into_iter(),
+        )
+        .await
+    } else {
+        all_courses(site, client, filter).await
+    };
+
+    let mut conn = if fresh {
+        establish_fresh_connection()
+    } else {
+        establish_connection()
+    };
+    let coverage = handle_results(&mut collector, &mut conn).await;
+    (coverage, collector.layout(), collector.is_partial())
+}
+
 fn read_courses(buf: PathBuf) -> Result<Vec<Record>, Box<dyn Error>> {
     let f = File::open(buf)?;
     let mut rdr = csv::Reader::from_reader(f);